@@ -0,0 +1,363 @@
+//! Budget alerts
+//!
+//! Daily, weekly and monthly spending limits, for all projects or a single one, kept
+//! in ~/.lovstudio/lovcode/budgets.json. A background task prices the current period
+//! of every budget from the session metadata cache whenever the projects watcher sees
+//! session files change, so growing sessions are picked up without reading whole
+//! transcripts again. When spend crosses one of a budget's
+//! thresholds it emits a `budget-alert` event and shows a system notification. The
+//! highest threshold alerted in each period is kept in budget-alerts.json, so every
+//! alert fires once per period, across restarts too.
+
+use crate::usage_report::{self, UsageRange};
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Least time between checks, so a running session's steady writes cost one check
+const MIN_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_THRESHOLDS: [u32; 2] = [80, 100];
+
+/// Held while checking, so the background task and a settings save never alert twice
+static CHECK_LOCK: Mutex<()> = Mutex::new(());
+
+/// Set by `request_check` and cleared by the background task when it wakes up
+static CHECK_PENDING: Mutex<bool> = Mutex::new(false);
+static CHECK_WAKE: Condvar = Condvar::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    /// Assigned when the budget is first saved
+    #[serde(default)]
+    pub id: String,
+    pub project_id: Option<String>, // None covers all projects
+    pub period: BudgetPeriod,
+    pub limit_usd: f64,
+    /// Percentages of the limit to alert at
+    #[serde(default = "default_thresholds")]
+    pub thresholds: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    pub budgets: Vec<Budget>,
+    /// Show a system notification besides the in-app event
+    pub system_notifications: bool,
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        Self {
+            budgets: Vec::new(),
+            system_notifications: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    /// 2025-06-01, 2025-W22 or 2025-06
+    pub period_key: String,
+    pub period_start: String, // YYYY-MM-DD
+    pub spent_usd: f64,
+    pub percent: f64,
+}
+
+/// Payload of the `budget-alert` event
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetAlert {
+    pub budget_id: String,
+    pub project_id: Option<String>,
+    pub project_path: Option<String>,
+    pub period: BudgetPeriod,
+    pub period_key: String,
+    pub threshold: u32,
+    pub limit_usd: f64,
+    pub spent_usd: f64,
+}
+
+/// Highest threshold alerted for a budget in its latest period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SentAlert {
+    period_key: String,
+    threshold: u32,
+}
+
+fn default_thresholds() -> Vec<u32> {
+    DEFAULT_THRESHOLDS.to_vec()
+}
+
+impl BudgetPeriod {
+    /// First day and key of the period containing `today`
+    fn current(self, today: NaiveDate) -> (NaiveDate, String) {
+        match self {
+            BudgetPeriod::Daily => (today, today.format("%Y-%m-%d").to_string()),
+            BudgetPeriod::Weekly => {
+                let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+                (monday, today.format("%G-W%V").to_string())
+            }
+            BudgetPeriod::Monthly => (today.with_day(1).unwrap_or(today), today.format("%Y-%m").to_string()),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "Daily",
+            BudgetPeriod::Weekly => "Weekly",
+            BudgetPeriod::Monthly => "Monthly",
+        }
+    }
+}
+
+fn get_settings_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("budgets.json")
+}
+
+fn get_sent_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("budget-alerts.json")
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+}
+
+pub fn load_settings() -> BudgetSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Validate and store budgets, giving new ones an ID. Returns what was stored.
+pub fn save_settings(mut settings: BudgetSettings) -> Result<BudgetSettings, String> {
+    for budget in settings.budgets.iter_mut() {
+        if !budget.limit_usd.is_finite() || budget.limit_usd <= 0.0 {
+            return Err(format!("Budget limit must be positive: {}", budget.limit_usd));
+        }
+        if budget.id.is_empty() {
+            budget.id = uuid::Uuid::new_v4().to_string();
+        }
+        budget.project_id = budget.project_id.take().filter(|id| !id.trim().is_empty());
+        budget.thresholds.retain(|threshold| *threshold > 0);
+        budget.thresholds.sort_unstable();
+        budget.thresholds.dedup();
+        if budget.thresholds.is_empty() {
+            budget.thresholds = default_thresholds();
+        }
+    }
+    write_json(&get_settings_path(), &settings)?;
+    Ok(settings)
+}
+
+fn load_sent() -> HashMap<String, SentAlert> {
+    fs::read_to_string(get_sent_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Spend of every budget in its current period
+pub fn statuses(budgets: &[Budget]) -> Result<Vec<BudgetStatus>, String> {
+    let today = Local::now().date_naive();
+    let periods: Vec<(NaiveDate, String)> = budgets.iter().map(|budget| budget.period.current(today)).collect();
+    let earliest = match periods.iter().map(|(start, _)| *start).min() {
+        Some(earliest) => earliest,
+        None => return Ok(Vec::new()),
+    };
+    // Daily costs since the earliest period start cover every budget's period
+    let range = UsageRange {
+        from: Some(earliest.format("%Y-%m-%d").to_string()),
+        to: Some(today.format("%Y-%m-%d").to_string()),
+    };
+    let costs = usage_report::daily_project_costs(&range)?;
+    let mut statuses = Vec::new();
+
+    for (budget, (start, period_key)) in budgets.iter().zip(periods) {
+        let spent_usd = costs
+            .iter()
+            .filter(|(project_id, _)| match &budget.project_id {
+                Some(id) => id == *project_id,
+                None => true,
+            })
+            .flat_map(|(_, days)| days.range(start..))
+            .map(|(_, cost)| cost)
+            .sum::<f64>();
+
+        statuses.push(BudgetStatus {
+            budget: budget.clone(),
+            period_key,
+            period_start: start.format("%Y-%m-%d").to_string(),
+            spent_usd,
+            percent: spent_usd / budget.limit_usd * 100.0,
+        });
+    }
+    Ok(statuses)
+}
+
+/// Alert on budgets whose spend crossed a threshold not yet alerted in this period
+pub fn check(app_handle: &AppHandle) -> Result<(), String> {
+    let _guard = CHECK_LOCK.lock().map_err(|e| e.to_string())?;
+    let settings = load_settings();
+    let mut sent = load_sent();
+    let before = sent.len();
+    sent.retain(|id, _| settings.budgets.iter().any(|budget| &budget.id == id));
+    let mut changed = sent.len() != before;
+
+    for status in statuses(&settings.budgets)? {
+        let budget = &status.budget;
+        let crossed = budget
+            .thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| status.percent >= *threshold as f64)
+            .max();
+        let threshold = match crossed {
+            Some(threshold) => threshold,
+            None => continue,
+        };
+        let already_sent = sent
+            .get(&budget.id)
+            .is_some_and(|s| s.period_key == status.period_key && s.threshold >= threshold);
+        if already_sent {
+            continue;
+        }
+
+        let alert = BudgetAlert {
+            budget_id: budget.id.clone(),
+            project_id: budget.project_id.clone(),
+            project_path: budget.project_id.as_deref().map(crate::decode_project_path),
+            period: budget.period,
+            period_key: status.period_key.clone(),
+            threshold,
+            limit_usd: budget.limit_usd,
+            spent_usd: status.spent_usd,
+        };
+        if let Err(e) = app_handle.emit("budget-alert", &alert) {
+            eprintln!("Failed to emit budget-alert event: {}", e);
+        }
+        if settings.system_notifications {
+            show_notification(&alert);
+        }
+
+        sent.insert(
+            budget.id.clone(),
+            SentAlert {
+                period_key: status.period_key,
+                threshold,
+            },
+        );
+        changed = true;
+    }
+
+    if changed {
+        write_json(&get_sent_path(), &sent)?;
+    }
+    Ok(())
+}
+
+/// Ask the background task to check again, e.g. because session files changed
+pub fn request_check() {
+    let mut pending = match CHECK_PENDING.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    *pending = true;
+    CHECK_WAKE.notify_one();
+}
+
+/// Check budgets at startup and then on every `request_check`, for as long as the app runs
+pub fn run(app_handle: AppHandle) {
+    loop {
+        if let Err(e) = check(&app_handle) {
+            eprintln!("Failed to check budgets: {}", e);
+        }
+        // Requests made meanwhile are handled together by the next check
+        std::thread::sleep(MIN_CHECK_INTERVAL);
+        let mut pending = match CHECK_PENDING.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        while !*pending {
+            pending = match CHECK_WAKE.wait(pending) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        *pending = false;
+    }
+}
+
+fn show_notification(alert: &BudgetAlert) {
+    let scope = alert
+        .project_path
+        .as_deref()
+        .map(|path| path.rsplit(['/', '\\']).next().unwrap_or(path).to_string())
+        .unwrap_or_else(|| "All projects".to_string());
+    let title = format!("{} budget: {}% used", alert.period.label(), alert.threshold);
+    let body = format!(
+        "{} spent ${:.2} of ${:.2} in {}",
+        scope, alert.spent_usd, alert.limit_usd, alert.period_key
+    );
+
+    // Text is passed through the environment so it never needs escaping. Waiting on the
+    // command reaps it, so no process is left behind.
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "display notification (system attribute \"LOVCODE_BODY\") with title (system attribute \"LOVCODE_TITLE\")",
+        ])
+        .env("LOVCODE_TITLE", &title)
+        .env("LOVCODE_BODY", &body)
+        .status();
+    // Toasts need a registered AppUserModelID; PowerShell's own is always there
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+             $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+             $text = $xml.GetElementsByTagName('text'); \
+             $text.Item(0).AppendChild($xml.CreateTextNode($env:LOVCODE_TITLE)) > $null; \
+             $text.Item(1).AppendChild($xml.CreateTextNode($env:LOVCODE_BODY)) > $null; \
+             [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+        ])
+        .env("LOVCODE_TITLE", &title)
+        .env("LOVCODE_BODY", &body)
+        .status();
+    #[cfg(target_os = "linux")]
+    let result = std::process::Command::new("notify-send")
+        .args(["--app-name=Lovcode", &title, &body])
+        .status();
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    let _ = (title, body);
+
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    match result {
+        Ok(status) if !status.success() => eprintln!("Failed to show budget notification: {}", status),
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to show budget notification: {}", e),
+    }
+}
@@ -4,6 +4,14 @@ use std::collections::HashSet;
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::sync::LazyLock;
+
+// 敏感信息正则 - 匹配硬编码的 API keys, tokens, passwords
+static SECRET_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(api[_-]?key|secret|password|token|credential|private[_-]?key)\s*[=:]\s*['"]([\w\-_./+=]{8,})['""]"#
+    ).unwrap()
+});
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TechStack {
@@ -246,11 +254,6 @@ fn parse_env_keys(path: &Path) -> Vec<String> {
 fn scan_for_leaked_secrets(project_path: &Path) -> Vec<LeakedSecret> {
     let mut secrets = Vec::new();
 
-    // 敏感信息正则 - 匹配硬编码的 API keys, tokens, passwords
-    let secret_pattern = Regex::new(
-        r#"(?i)(api[_-]?key|secret|password|token|credential|private[_-]?key)\s*[=:]\s*['"]([\w\-_./+=]{8,})['""]"#
-    ).unwrap();
-
     // 要扫描的文件扩展名
     let scan_extensions = ["ts", "tsx", "js", "jsx", "py", "rs", "go", "java", "rb"];
 
@@ -262,11 +265,30 @@ fn scan_for_leaked_secrets(project_path: &Path) -> Vec<LeakedSecret> {
         "chunks", "ssr", "static",  // Next.js 内部目录
     ];
 
-    scan_directory(project_path, &secret_pattern, &scan_extensions, &exclude_dirs, &mut secrets);
+    scan_directory(project_path, &SECRET_PATTERN, &scan_extensions, &exclude_dirs, &mut secrets);
 
     secrets
 }
 
+/// 过滤掉明显的占位符
+fn is_placeholder_secret(value: &str) -> bool {
+    value.contains("your_") || value.contains("xxx") || value.contains("placeholder") || value == "undefined" || value == "null"
+}
+
+/// 脱敏文本中的敏感信息，规则与 scan_for_leaked_secrets 相同
+pub fn redact_secrets(text: &str) -> String {
+    SECRET_PATTERN
+        .replace_all(text, |cap: &regex::Captures| {
+            let value = cap.get(2).map(|m| m.as_str()).unwrap_or("");
+            if is_placeholder_secret(value) {
+                cap[0].to_string()
+            } else {
+                cap[0].replacen(value, "[REDACTED]", 1)
+            }
+        })
+        .to_string()
+}
+
 fn scan_directory(
     dir: &Path,
     pattern: &Regex,
@@ -314,8 +336,7 @@ fn scan_directory(
                         let key_name = cap.get(1).map(|m| m.as_str()).unwrap_or("unknown");
                         let value = cap.get(2).map(|m| m.as_str()).unwrap_or("");
 
-                        // 过滤掉明显的占位符
-                        if value.contains("your_") || value.contains("xxx") || value.contains("placeholder") || value == "undefined" || value == "null" {
+                        if is_placeholder_secret(value) {
                             continue;
                         }
 
@@ -1,6 +1,16 @@
+mod budget_alerts;
 mod diagnostics;
 mod hook_watcher;
+mod pricing;
 mod pty_manager;
+mod semantic_index;
+mod session_cache;
+mod session_export;
+mod session_lines;
+mod session_meta;
+mod session_sources;
+mod session_trash;
+mod usage_report;
 mod workspace_store;
 
 use jieba_rs::Jieba;
@@ -10,12 +20,13 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, Sender};
 use std::sync::LazyLock;
 use std::sync::Mutex;
 use std::time::Duration;
 use tantivy::collector::TopDocs;
 use tantivy::query::QueryParser;
+use tantivy::snippet::{Snippet, SnippetGenerator};
 use tantivy::schema::{self, Value as TantivyValue, *};
 use tantivy::tokenizer::{LowerCaser, TextAnalyzer, Token, TokenStream, Tokenizer};
 use tantivy::{doc, Index, IndexWriter, ReloadPolicy};
@@ -102,6 +113,13 @@ impl TokenStream for JiebaTokenStream {
 // Global search index state
 static SEARCH_INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
 
+// Serializes index writers (full sync and the projects watcher) and caches the manifest
+static SEARCH_INDEX_MANIFEST: Mutex<Option<IndexManifest>> = Mutex::new(None);
+
+// Set while an outdated index is being rebuilt in the background
+static SEARCH_INDEX_REBUILDING: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
 // Distill watch state
 static DISTILL_WATCH_ENABLED: std::sync::atomic::AtomicBool =
     std::sync::atomic::AtomicBool::new(true);
@@ -130,26 +148,62 @@ fn get_command_stats_path() -> PathBuf {
 
 const JIEBA_TOKENIZER_NAME: &str = "jieba";
 
+/// Bump whenever `create_schema` or the indexed document layout changes.
+/// An index written with another version is rebuilt from scratch.
+const SEARCH_SCHEMA_VERSION: u32 = 5;
+
+fn get_schema_version_path() -> PathBuf {
+    get_index_dir().join("schema_version")
+}
+
+fn read_schema_version() -> Option<u32> {
+    fs::read_to_string(get_schema_version_path()).ok()?.trim().parse().ok()
+}
+
 fn create_schema() -> Schema {
     let mut schema_builder = Schema::builder();
 
     // Use custom jieba tokenizer for content fields to support Chinese
+    let text_indexing = TextFieldIndexing::default()
+        .set_tokenizer(JIEBA_TOKENIZER_NAME)
+        .set_index_option(schema::IndexRecordOption::WithFreqsAndPositions);
     let text_options = TextOptions::default()
-        .set_indexing_options(
-            TextFieldIndexing::default()
-                .set_tokenizer(JIEBA_TOKENIZER_NAME)
-                .set_index_option(schema::IndexRecordOption::WithFreqsAndPositions),
-        )
+        .set_indexing_options(text_indexing.clone())
         .set_stored();
 
+    // Message time, used for date range filters
+    let date_options = DateOptions::from(INDEXED)
+        .set_stored()
+        .set_fast()
+        .set_precision(DateTimePrecision::Milliseconds);
+
     schema_builder.add_text_field("uuid", STRING | STORED);
     schema_builder.add_text_field("content", text_options.clone());
-    schema_builder.add_text_field("role", STRING | STORED);
+    schema_builder.add_text_field("role", STRING | STORED | FAST);
     schema_builder.add_text_field("project_id", STRING | STORED);
     schema_builder.add_text_field("project_path", STRING | STORED);
-    schema_builder.add_text_field("session_id", STRING | STORED);
-    schema_builder.add_text_field("session_summary", text_options);
-    schema_builder.add_text_field("timestamp", STRING | STORED);
+    schema_builder.add_text_field("session_id", STRING | STORED | FAST);
+    schema_builder.add_text_field("session_summary", text_options.clone());
+    schema_builder.add_date_field("timestamp", date_options);
+    schema_builder.add_bool_field("is_tool", INDEXED | STORED);
+    schema_builder.add_u64_field("line_number", STORED);
+    // Task subagent transcripts (agent-*.jsonl) and the session that spawned them
+    schema_builder.add_bool_field("is_subagent", INDEXED | STORED);
+    schema_builder.add_text_field("parent_session_id", STRING | STORED);
+    // Coding agent that wrote the session, see `Session::source`
+    schema_builder.add_text_field("source", STRING | STORED);
+    // Session tags from lovcode's metadata store, one facet per tag
+    schema_builder.add_facet_field("tags", STORED);
+
+    // Tool activity of the message, e.g. `tool_name:Bash AND tool_input:"cargo publish"`
+    schema_builder.add_text_field("tool_name", STRING | STORED);
+    schema_builder.add_text_field("tool_input", text_options.clone());
+    schema_builder.add_text_field("file_paths", text_options);
+    // Tool output is searchable but not stored, it can be large
+    schema_builder.add_text_field(
+        "tool_output",
+        TextOptions::default().set_indexing_options(text_indexing),
+    );
     schema_builder.build()
 }
 
@@ -168,16 +222,32 @@ pub struct Project {
     pub last_active: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SessionUsage {
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_tokens: u64,
     pub cache_read_tokens: u64,
     pub cost_usd: f64, // estimated cost in USD
+    /// Tokens and cost per `message.model`
+    #[serde(default)]
+    pub by_model: std::collections::BTreeMap<String, ModelUsage>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ModelUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_5m_tokens: u64,
+    pub cache_creation_1h_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// `Session::source` of Claude Code sessions
+const CLAUDE_SESSION_SOURCE: &str = "claude";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
     pub project_id: String,
@@ -186,9 +256,12 @@ pub struct Session {
     pub message_count: usize,
     pub last_modified: u64,
     pub usage: Option<SessionUsage>,
+    pub source: String, // coding agent that wrote the session, e.g. "claude", "codex"
+    #[serde(flatten)]
+    pub meta: session_meta::SessionMeta, // tags, star, note and title set in lovcode
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub uuid: String,
     pub role: String,
@@ -199,6 +272,47 @@ pub struct Message {
     pub line_number: usize,
 }
 
+/// A typed block of message content
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+        result_uuid: Option<String>, // message carrying the matching tool_result
+    },
+    ToolResult {
+        tool_use_id: String,
+        tool_name: Option<String>, // name of the matching tool_use
+        is_error: bool,
+        content: Vec<ContentBlock>,
+    },
+    Image {
+        media_type: Option<String>,
+        source: String, // data: URL for inline images, otherwise the image URL
+    },
+}
+
+/// Message with its full content blocks, as returned by `get_session_transcript`
+#[derive(Debug, Serialize)]
+pub struct TranscriptMessage {
+    pub uuid: String,
+    pub parent_uuid: Option<String>,
+    pub is_sidechain: bool,
+    pub role: String,
+    pub timestamp: String,
+    pub is_meta: bool,
+    pub line_number: usize,
+    pub blocks: Vec<ContentBlock>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub uuid: String,
@@ -209,6 +323,7 @@ pub struct ChatMessage {
     pub project_path: String,
     pub session_id: String,
     pub session_summary: Option<String>,
+    pub parent_session_id: Option<String>, // set for messages of subagents
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -227,6 +342,14 @@ struct RawLine {
     timestamp: Option<String>,
     #[serde(rename = "isMeta")]
     is_meta: Option<bool>,
+    #[serde(rename = "parentUuid")]
+    parent_uuid: Option<String>,
+    #[serde(rename = "isSidechain")]
+    is_sidechain: Option<bool>,
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -235,13 +358,23 @@ struct RawUsage {
     output_tokens: Option<u64>,
     cache_creation_input_tokens: Option<u64>,
     cache_read_input_tokens: Option<u64>,
+    /// Split of `cache_creation_input_tokens` by cache lifetime
+    cache_creation: Option<RawCacheCreation>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawCacheCreation {
+    ephemeral_5m_input_tokens: Option<u64>,
+    ephemeral_1h_input_tokens: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawMessage {
+    id: Option<String>,
     role: Option<String>,
     content: Option<serde_json::Value>,
     usage: Option<RawUsage>,
+    model: Option<String>,
 }
 
 /// Entry from history.jsonl - used as fast session index
@@ -341,10 +474,106 @@ fn encode_project_path(path: &str) -> String {
     path.replace("/.", "--").replace("/", "-")
 }
 
+/// Session lines read when looking for the `cwd` a project was started in
+const PROJECT_CWD_HEAD_LINES: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct RawCwdLine {
+    cwd: Option<String>,
+}
+
+/// First `cwd` recorded in the head of a session file
+fn read_session_cwd(path: &Path) -> Option<String> {
+    let mut cwd = None;
+    let mut lines = 0;
+    let _ = for_each_complete_line(path, 0, |line| {
+        lines += 1;
+        cwd = serde_json::from_str::<RawCwdLine>(line).ok().and_then(|l| l.cwd);
+        cwd.is_none() && lines < PROJECT_CWD_HEAD_LINES
+    });
+    cwd
+}
+
+/// Project ID Claude Code uses for a working directory: every character other than
+/// ASCII letters, digits and `-` becomes `-`
+fn encode_cwd_project_id(path: &str) -> String {
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect()
+}
+
+fn cwd_matches_project_id(path: &str, id: &str) -> bool {
+    encode_cwd_project_id(path) == id
+}
+
+/// Project path from the cwd recorded in its sessions, newest session first
+fn resolve_project_path_from_sessions(id: &str) -> Option<String> {
+    let project_dir = get_claude_dir().join("projects").join(id);
+    let dir_mtime = fs::metadata(&project_dir)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    if let Some(cached) = session_cache::project_path(id) {
+        if cached.path.is_some() || cached.dir_mtime == dir_mtime {
+            return cached.path;
+        }
+    }
+
+    let mut session_files: Vec<(u64, PathBuf)> = fs::read_dir(&project_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "jsonl"))
+        .map(|entry| {
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            (modified, entry.path())
+        })
+        .collect();
+    session_files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    // A cwd that does not encode to the ID (e.g. after a `cd`) is only a last resort
+    let mut fallback = None;
+    let mut resolved = None;
+    for (_, path) in &session_files {
+        if let Some(cwd) = read_session_cwd(path) {
+            if cwd_matches_project_id(&cwd, id) {
+                resolved = Some(cwd);
+                break;
+            }
+            fallback.get_or_insert(cwd);
+        }
+    }
+    let resolved = resolved.or(fallback);
+
+    session_cache::set_project_path(
+        id,
+        session_cache::CachedProjectPath {
+            path: resolved.clone(),
+            dir_mtime,
+        },
+    );
+    resolved
+}
+
+/// Resolve a project ID to its filesystem path, preferring the cwd recorded in its
+/// sessions over decoding the ID
+fn decode_project_path(id: &str) -> String {
+    resolve_project_path_from_sessions(id).unwrap_or_else(|| decode_project_path_heuristic(id))
+}
+
 /// Decode project ID to actual filesystem path.
 /// Claude Code encodes: `/` -> `-`, and `.` -> `-`
 /// So `/.` becomes `--`, but `-` in directory names is NOT escaped
-fn decode_project_path(id: &str) -> String {
+fn decode_project_path_heuristic(id: &str) -> String {
     // First, handle `--` which means `/.` (hidden directories like .claude)
     // Replace `--` with a placeholder, then `-` with `/`, then restore `/.`
     let base = id
@@ -455,6 +684,8 @@ async fn list_projects() -> Result<Vec<Project>, String> {
             }
         }
 
+        // Persist project paths resolved above
+        session_cache::flush();
         projects.sort_by(|a, b| b.last_active.cmp(&a.last_active));
         Ok(projects)
     })
@@ -462,6 +693,8 @@ async fn list_projects() -> Result<Vec<Project>, String> {
     .map_err(|e| e.to_string())?
 }
 
+/// Sessions of a project. Subagent transcripts are left out; they are listed under
+/// the session that started them by `list_subagents`.
 #[tauri::command]
 async fn list_sessions(project_id: String) -> Result<Vec<Session>, String> {
     tauri::async_runtime::spawn_blocking(move || {
@@ -471,38 +704,35 @@ async fn list_sessions(project_id: String) -> Result<Vec<Session>, String> {
             return Err("Project not found".to_string());
         }
 
-        let mut sessions = Vec::new();
-
+        let mut paths = Vec::new();
         for entry in fs::read_dir(&project_dir).map_err(|e| e.to_string())? {
             let entry = entry.map_err(|e| e.to_string())?;
             let path = entry.path();
             let name = path.file_name().unwrap().to_string_lossy().to_string();
-
             if name.ends_with(".jsonl") && !name.starts_with("agent-") {
-                let session_id = name.trim_end_matches(".jsonl").to_string();
-
-                // Only read head for summary (much faster)
-                let (summary, message_count) = read_session_head(&path, 20);
-
-                let metadata = fs::metadata(&path).ok();
-                let last_modified = metadata
-                    .and_then(|m| m.modified().ok())
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-
-                sessions.push(Session {
-                    id: session_id,
-                    project_id: project_id.clone(),
-                    project_path: None,
-                    summary,
-                    message_count,
-                    last_modified,
-                    usage: None,
-                });
+                paths.push(path);
             }
         }
 
+        // Summary, counts and usage come from the metadata cache
+        let cached = lookup_sessions_with_subagents(&paths);
+        let mut sessions: Vec<Session> = paths
+            .iter()
+            .zip(cached)
+            .map(|(path, cached)| Session {
+                id: path.file_stem().unwrap().to_string_lossy().to_string(),
+                project_id: project_id.clone(),
+                project_path: None,
+                summary: cached.summary,
+                message_count: cached.message_count,
+                last_modified: cached.mtime / 1000,
+                usage: Some(cached.usage),
+                source: CLAUDE_SESSION_SOURCE.to_string(),
+                meta: Default::default(),
+            })
+            .collect();
+
+        session_meta::apply(&mut sessions);
         sessions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
         Ok(sessions)
     })
@@ -510,55 +740,156 @@ async fn list_sessions(project_id: String) -> Result<Vec<Session>, String> {
     .map_err(|e| e.to_string())?
 }
 
-/// Pricing constants (USD per 1M tokens) - Claude Opus 4.5 pricing as baseline
-const PRICE_INPUT_PER_M: f64 = 15.0;
-const PRICE_OUTPUT_PER_M: f64 = 75.0;
-const PRICE_CACHE_WRITE_PER_M: f64 = 3.75; // cache creation
-const PRICE_CACHE_READ_PER_M: f64 = 0.30;  // cache read
+impl ModelUsage {
+    /// Usage recorded on one assistant message
+    fn from_raw(raw: &RawUsage) -> Self {
+        let cache_creation = raw.cache_creation_input_tokens.unwrap_or(0);
+        // Without the split, all cache writes are the default 5 minute tier
+        let cache_creation_1h = raw
+            .cache_creation
+            .as_ref()
+            .and_then(|c| c.ephemeral_1h_input_tokens)
+            .unwrap_or(0);
+        let cache_creation_5m = raw
+            .cache_creation
+            .as_ref()
+            .and_then(|c| c.ephemeral_5m_input_tokens)
+            .unwrap_or_else(|| cache_creation.saturating_sub(cache_creation_1h));
+
+        Self {
+            input_tokens: raw.input_tokens.unwrap_or(0),
+            output_tokens: raw.output_tokens.unwrap_or(0),
+            cache_creation_5m_tokens: cache_creation_5m,
+            cache_creation_1h_tokens: cache_creation_1h,
+            cache_read_tokens: raw.cache_read_input_tokens.unwrap_or(0),
+            cost_usd: 0.0,
+        }
+    }
 
-/// Calculate cost from token counts
-fn calculate_cost(usage: &SessionUsage) -> f64 {
-    let input_cost = (usage.input_tokens as f64 / 1_000_000.0) * PRICE_INPUT_PER_M;
-    let output_cost = (usage.output_tokens as f64 / 1_000_000.0) * PRICE_OUTPUT_PER_M;
-    let cache_write_cost = (usage.cache_creation_tokens as f64 / 1_000_000.0) * PRICE_CACHE_WRITE_PER_M;
-    let cache_read_cost = (usage.cache_read_tokens as f64 / 1_000_000.0) * PRICE_CACHE_READ_PER_M;
-    input_cost + output_cost + cache_write_cost + cache_read_cost
+    fn add(&mut self, other: &ModelUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_5m_tokens += other.cache_creation_5m_tokens;
+        self.cache_creation_1h_tokens += other.cache_creation_1h_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cost_usd += other.cost_usd;
+    }
 }
 
-/// Read usage data from a session file
-fn read_session_usage(path: &Path) -> SessionUsage {
-    use std::io::{BufRead, BufReader};
+/// Add usage of one model to the totals and its `by_model` entry. Costs are filled
+/// in separately by `pricing::apply_costs`.
+fn add_model_usage(usage: &mut SessionUsage, model: &str, delta: &ModelUsage) {
+    usage.input_tokens += delta.input_tokens;
+    usage.output_tokens += delta.output_tokens;
+    usage.cache_creation_tokens += delta.cache_creation_5m_tokens + delta.cache_creation_1h_tokens;
+    usage.cache_read_tokens += delta.cache_read_tokens;
+    usage.cost_usd += delta.cost_usd;
+    usage.by_model.entry(model.to_string()).or_default().add(delta);
+}
 
-    let file = match fs::File::open(path) {
-        Ok(f) => f,
-        Err(_) => return SessionUsage::default(),
-    };
+/// Metadata of session files, looked up together with the subagents of their projects
+/// so messages replayed by resumed sessions count once. Subagent usage is rolled into
+/// the session that spawned it. Listings and `get_sessions_usage` all go through here,
+/// so a session shows the same cost everywhere.
+fn lookup_sessions_with_subagents(session_paths: &[PathBuf]) -> Vec<session_cache::CachedSessionFile> {
+    let mut project_dirs: Vec<&Path> = session_paths.iter().filter_map(|path| path.parent()).collect();
+    project_dirs.sort();
+    project_dirs.dedup();
+    let subagent_paths: Vec<PathBuf> = project_dirs.into_iter().flat_map(list_subagent_files).collect();
 
-    let reader = BufReader::new(file);
-    let mut usage = SessionUsage::default();
+    let all_paths: Vec<PathBuf> = session_paths.iter().chain(&subagent_paths).cloned().collect();
+    let mut cached = session_cache::lookup_files(&all_paths);
+    let subagents = cached.split_off(session_paths.len());
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
+    let index: HashMap<&Path, usize> = session_paths
+        .iter()
+        .enumerate()
+        .map(|(idx, path)| (path.as_path(), idx))
+        .collect();
+    for (path, subagent) in subagent_paths.iter().zip(subagents) {
+        let parent_path = match (session_file_project_dir(path), read_subagent_parent(path)) {
+            (Some(project_dir), Some(parent)) => project_dir.join(format!("{}.jsonl", parent)),
+            _ => continue,
         };
-        if let Ok(parsed) = serde_json::from_str::<RawLine>(&line) {
-            // Only assistant messages have usage data
-            if parsed.line_type.as_deref() == Some("assistant") {
-                if let Some(msg) = &parsed.message {
-                    if let Some(u) = &msg.usage {
-                        usage.input_tokens += u.input_tokens.unwrap_or(0);
-                        usage.output_tokens += u.output_tokens.unwrap_or(0);
-                        usage.cache_creation_tokens += u.cache_creation_input_tokens.unwrap_or(0);
-                        usage.cache_read_tokens += u.cache_read_input_tokens.unwrap_or(0);
-                    }
-                }
-            }
+        if let Some(&idx) = index.get(parent_path.as_path()) {
+            add_session_usage(&mut cached[idx].usage, &subagent.usage);
         }
     }
+    cached
+}
+
+fn add_session_usage(total: &mut SessionUsage, usage: &SessionUsage) {
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cache_creation_tokens += usage.cache_creation_tokens;
+    total.cache_read_tokens += usage.cache_read_tokens;
+    total.cost_usd += usage.cost_usd;
+    for (model, model_usage) in &usage.by_model {
+        total.by_model.entry(model.clone()).or_default().add(model_usage);
+    }
+}
+
+/// Tokens and cost over a date range, grouped by day, week, project, model or session
+#[tauri::command]
+async fn get_usage_report(
+    range: usage_report::UsageRange,
+    group_by: usage_report::UsageGroupBy,
+) -> Result<usage_report::UsageReport, String> {
+    tauri::async_runtime::spawn_blocking(move || usage_report::build_report(&range, group_by))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Write usage rows for spreadsheets to `path` as CSV or JSON. Returns the number of rows.
+#[tauri::command]
+async fn export_usage(
+    range: usage_report::UsageRange,
+    group_by: usage_report::UsageGroupBy,
+    format: usage_report::UsageExportFormat,
+    path: String,
+) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let rows = usage_report::export_rows(&range, group_by)?;
+        let content = usage_report::render_rows(&rows, format)?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(rows.len())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn get_budget_settings() -> budget_alerts::BudgetSettings {
+    budget_alerts::load_settings()
+}
+
+/// Store budgets and check them right away, so a lowered limit alerts without waiting
+#[tauri::command]
+fn save_budget_settings(
+    app_handle: tauri::AppHandle,
+    settings: budget_alerts::BudgetSettings,
+) -> Result<budget_alerts::BudgetSettings, String> {
+    let settings = budget_alerts::save_settings(settings)?;
+    std::thread::spawn(move || {
+        if let Err(e) = budget_alerts::check(&app_handle) {
+            eprintln!("Failed to check budgets: {}", e);
+        }
+    });
+    Ok(settings)
+}
+
+/// Spend of every budget in its current period
+#[tauri::command]
+async fn get_budget_status() -> Result<Vec<budget_alerts::BudgetStatus>, String> {
+    tauri::async_runtime::spawn_blocking(|| budget_alerts::statuses(&budget_alerts::load_settings().budgets))
+        .await
+        .map_err(|e| e.to_string())?
+}
 
-    usage.cost_usd = calculate_cost(&usage);
-    usage
+/// Prices per model in effect: the bundled table with the user's overrides
+#[tauri::command]
+fn get_pricing_table() -> pricing::PricingTable {
+    (*pricing::table()).clone()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -576,17 +907,33 @@ async fn get_sessions_usage(project_id: String) -> Result<Vec<SessionUsageEntry>
             return Err("Project not found".to_string());
         }
 
-        let mut results = Vec::new();
-
+        let mut session_paths = Vec::new();
         for entry in fs::read_dir(&project_dir).map_err(|e| e.to_string())? {
             let entry = entry.map_err(|e| e.to_string())?;
             let path = entry.path();
             let name = path.file_name().unwrap().to_string_lossy().to_string();
-
             if name.ends_with(".jsonl") && !name.starts_with("agent-") {
-                let session_id = name.trim_end_matches(".jsonl").to_string();
-                let usage = read_session_usage(&path);
-                results.push(SessionUsageEntry { session_id, usage });
+                session_paths.push(path);
+            }
+        }
+
+        let cached = lookup_sessions_with_subagents(&session_paths);
+        let mut results: Vec<SessionUsageEntry> = session_paths
+            .iter()
+            .zip(cached)
+            .map(|(path, cached)| SessionUsageEntry {
+                session_id: path.file_stem().unwrap().to_string_lossy().to_string(),
+                usage: cached.usage,
+            })
+            .collect();
+
+        // Sessions of other coding agents in the same working directory
+        for session in session_sources::list_imported_sessions() {
+            if session.project_id == project_id {
+                results.push(SessionUsageEntry {
+                    session_id: session.id,
+                    usage: session.usage.unwrap_or_default(),
+                });
             }
         }
 
@@ -596,6 +943,201 @@ async fn get_sessions_usage(project_id: String) -> Result<Vec<SessionUsageEntry>
     .map_err(|e| e.to_string())?
 }
 
+/// A Task tool call in a parent session
+struct TaskCall {
+    tool_use_id: String,
+    prompt: String,
+    description: Option<String>,
+    subagent_type: Option<String>,
+}
+
+/// Task calls of a session, and the tool_use id recorded for each agent id in the results
+fn read_task_calls(path: &Path) -> (Vec<TaskCall>, HashMap<String, String>) {
+    let mut calls = Vec::new();
+    let mut agent_tool_uses = HashMap::new();
+
+    let _ = for_each_complete_line(path, 0, |line| {
+        if !line.contains("\"Task\"") && !line.contains("\"Agent\"") && !line.contains("agentId") {
+            return true;
+        }
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(_) => return true,
+        };
+        let blocks = value
+            .pointer("/message/content")
+            .and_then(|c| c.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let get_str = |v: &Value, key: &str| v.get(key).and_then(|s| s.as_str()).map(String::from);
+
+        for block in &blocks {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") if matches!(block.get("name").and_then(|n| n.as_str()), Some("Task" | "Agent")) => {
+                    let input = block.get("input").cloned().unwrap_or(Value::Null);
+                    calls.push(TaskCall {
+                        tool_use_id: get_str(block, "id").unwrap_or_default(),
+                        prompt: get_str(&input, "prompt").unwrap_or_default(),
+                        description: get_str(&input, "description"),
+                        subagent_type: get_str(&input, "subagent_type"),
+                    });
+                }
+                Some("tool_result") => {
+                    let agent_id = value.pointer("/toolUseResult/agentId").and_then(|a| a.as_str());
+                    if let (Some(agent_id), Some(tool_use_id)) = (agent_id, get_str(block, "tool_use_id")) {
+                        agent_tool_uses.insert(agent_id.to_string(), tool_use_id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    });
+
+    (calls, agent_tool_uses)
+}
+
+/// Text of the first user message, the prompt a subagent was started with
+fn read_first_user_text(path: &Path) -> Option<String> {
+    let mut text = None;
+    let _ = for_each_complete_line(path, 0, |line| {
+        if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
+            if parsed.line_type.as_deref() == Some("user") {
+                text = parsed.message.map(|m| extract_content_with_meta(&m.content).0);
+                return false;
+            }
+        }
+        true
+    });
+    text
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubagentSession {
+    pub id: String, // file stem, accepted as session_id by the message commands
+    pub agent_id: String,
+    pub parent_session_id: String,
+    pub tool_use_id: Option<String>, // Task tool_use in the parent session that started it
+    pub description: Option<String>,
+    pub subagent_type: Option<String>,
+    pub summary: Option<String>,
+    pub message_count: usize,
+    pub last_modified: u64,
+    pub usage: SessionUsage,
+}
+
+/// Subagents started by a session, linked to the Task calls that spawned them
+#[tauri::command]
+async fn list_subagents(project_id: String, session_id: String) -> Result<Vec<SubagentSession>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let project_dir = get_claude_dir().join("projects").join(&project_id);
+        if !project_dir.exists() {
+            return Err("Project not found".to_string());
+        }
+
+        let (calls, agent_tool_uses) = read_task_calls(&get_session_path(&project_id, &session_id));
+        let paths: Vec<PathBuf> = list_subagent_files(&project_dir)
+            .into_iter()
+            .filter(|path| read_subagent_parent(path).as_deref() == Some(session_id.as_str()))
+            .collect();
+        // Summary, counts and usage of all of them in one lookup, deduplicated against each other
+        let cached = session_cache::lookup_files(&paths);
+        let mut subagents = Vec::new();
+
+        for (path, cached) in paths.into_iter().zip(cached) {
+            let id = path.file_stem().unwrap().to_string_lossy().to_string();
+            let agent_id = id.trim_start_matches("agent-").to_string();
+
+            // Prefer the agent id recorded in the Task result, fall back to the prompt
+            let call = match agent_tool_uses.get(&agent_id) {
+                Some(tool_use_id) => calls.iter().find(|c| &c.tool_use_id == tool_use_id),
+                None => read_first_user_text(&path)
+                    .and_then(|prompt| calls.iter().find(|c| c.prompt.trim() == prompt.trim())),
+            };
+
+            subagents.push(SubagentSession {
+                id,
+                agent_id,
+                parent_session_id: session_id.clone(),
+                tool_use_id: call.map(|c| c.tool_use_id.clone()),
+                description: call.and_then(|c| c.description.clone()),
+                subagent_type: call.and_then(|c| c.subagent_type.clone()),
+                summary: cached.summary,
+                message_count: cached.message_count,
+                last_modified: cached.mtime / 1000,
+                usage: cached.usage,
+            });
+        }
+
+        subagents.sort_by_key(|s| s.last_modified);
+        Ok(subagents)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Task subagent transcripts are written as agent-<id>.jsonl, either next to the
+/// sessions or under <session_id>/subagents/
+fn is_subagent_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        name.starts_with("agent-") && name.ends_with(".jsonl")
+    })
+}
+
+/// Subagent transcripts under <session_id>/subagents/ of a project
+fn list_nested_subagent_files(project_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(project_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .flat_map(|entry| fs::read_dir(entry.path().join("subagents")).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .filter(|path| is_subagent_file(path))
+        .collect()
+}
+
+/// Subagent transcripts of a project, in both layouts
+fn list_subagent_files(project_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(project_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_subagent_file(path))
+        .collect();
+    files.extend(list_nested_subagent_files(project_dir));
+    files
+}
+
+/// Project directory of a session file, for both `<project>/<id>.jsonl` and
+/// `<project>/<session_id>/subagents/agent-<id>.jsonl`
+fn session_file_project_dir(path: &Path) -> Option<&Path> {
+    let parent = path.parent()?;
+    if parent.file_name()? == "subagents" {
+        parent.parent()?.parent()
+    } else {
+        Some(parent)
+    }
+}
+
+/// Session that spawned a subagent: its directory in the nested layout, otherwise the
+/// sessionId recorded on the subagent's lines
+fn read_subagent_parent(path: &Path) -> Option<String> {
+    let parent = path.parent()?;
+    if parent.file_name()? == "subagents" {
+        return Some(parent.parent()?.file_name()?.to_string_lossy().to_string());
+    }
+
+    let mut session_id = None;
+    let _ = for_each_complete_line(path, 0, |line| {
+        session_id = serde_json::from_str::<RawLine>(line).ok().and_then(|p| p.session_id);
+        session_id.is_none()
+    });
+    session_id
+}
+
 /// Read only the first N lines of a session file to get summary (much faster than reading entire file)
 fn read_session_head(path: &Path, max_lines: usize) -> (Option<String>, usize) {
     use std::io::{BufRead, BufReader};
@@ -705,150 +1247,80 @@ fn restore_slash_command(content: &str) -> String {
     }
 }
 
-/// Build session index from history.jsonl (fast: only reads one file)
-fn build_session_index_from_history() -> HashMap<(String, String), (u64, Option<String>)> {
-    use std::io::{BufRead, BufReader};
-
-    let history_path = get_claude_dir().join("history.jsonl");
-    let mut index: HashMap<(String, String), (u64, Option<String>)> = HashMap::new();
-
-    let file = match fs::File::open(&history_path) {
-        Ok(f) => f,
-        Err(_) => return index,
-    };
-
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-        if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&line) {
-            if let (Some(session_id), Some(project), Some(timestamp)) =
-                (entry.session_id, entry.project, entry.timestamp)
-            {
-                let project_id = encode_project_path(&project);
-                // Keep the latest timestamp and display for each session
-                index
-                    .entry((project_id, session_id))
-                    .and_modify(|(ts, disp)| {
-                        if timestamp > *ts {
-                            *ts = timestamp;
-                            *disp = entry.display.clone();
-                        }
-                    })
-                    .or_insert((timestamp, entry.display));
-            }
-        }
-    }
-
-    index
-}
-
 #[tauri::command]
 async fn list_all_sessions() -> Result<Vec<Session>, String> {
     tauri::async_runtime::spawn_blocking(|| {
         let projects_dir = get_claude_dir().join("projects");
 
         if !projects_dir.exists() {
-            return Ok(vec![]);
+            let mut sessions = session_sources::list_imported_sessions();
+            session_meta::apply(&mut sessions);
+            return Ok(sessions);
         }
 
-        // Build index from history.jsonl first (fast)
-        let history_index = build_session_index_from_history();
-
-        let mut all_sessions = Vec::new();
-        let mut seen_sessions: std::collections::HashSet<(String, String)> =
-            std::collections::HashSet::new();
-
-        // First pass: use history index for sessions with sessionId
-        for ((project_id, session_id), (timestamp, display)) in &history_index {
-            let session_path = projects_dir
-                .join(project_id)
-                .join(format!("{}.jsonl", session_id));
+        // history.jsonl gives display text for sessions without a summary
+        let history_index = session_cache::history_index();
 
-            if !session_path.exists() {
+        // (project_id, session_id, path) of every session file
+        let mut session_files = Vec::new();
+        for project_entry in fs::read_dir(&projects_dir).into_iter().flatten().flatten() {
+            let project_path = project_entry.path();
+            if !project_path.is_dir() {
                 continue;
             }
+            let project_id = project_path.file_name().unwrap().to_string_lossy().to_string();
 
-            seen_sessions.insert((project_id.clone(), session_id.clone()));
+            for entry in fs::read_dir(&project_path).into_iter().flatten().flatten() {
+                let path = entry.path();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                if name.ends_with(".jsonl") && !name.starts_with("agent-") {
+                    let session_id = name.trim_end_matches(".jsonl").to_string();
+                    session_files.push((project_id.clone(), session_id, path));
+                }
+            }
+        }
 
-            // Only read head for summary (first 20 lines should be enough)
-            let (summary, head_msg_count) = read_session_head(&session_path, 20);
+        let paths: Vec<PathBuf> = session_files.iter().map(|(_, _, path)| path.clone()).collect();
+        let cached = lookup_sessions_with_subagents(&paths);
+        let mut display_paths: HashMap<String, String> = HashMap::new();
 
+        let mut all_sessions = Vec::new();
+        for ((project_id, session_id, _), cached) in session_files.into_iter().zip(cached) {
+            let history = history_index.get(&(project_id.clone(), session_id.clone()));
             // Use display as fallback summary (also needs restore_slash_command)
-            let final_summary = summary.or_else(|| display.clone().map(|d| restore_slash_command(&d)));
-
-            // Use file mtime for accurate last_modified
-            let metadata = fs::metadata(&session_path).ok();
-            let last_modified = metadata
-                .and_then(|m| m.modified().ok())
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs())
-                .unwrap_or(*timestamp / 1000); // fallback to history timestamp
-
-            let display_path = decode_project_path(project_id);
+            let summary = cached.summary.or_else(|| {
+                history
+                    .and_then(|(_, display)| display.as_deref())
+                    .map(restore_slash_command)
+            });
+            let last_modified = if cached.mtime > 0 {
+                cached.mtime / 1000
+            } else {
+                history.map(|(timestamp, _)| timestamp / 1000).unwrap_or(0)
+            };
+            let display_path = display_paths
+                .entry(project_id.clone())
+                .or_insert_with(|| decode_project_path(&project_id))
+                .clone();
 
             all_sessions.push(Session {
-                id: session_id.clone(),
-                project_id: project_id.clone(),
-                project_path: Some(display_path),
-                summary: final_summary,
-                message_count: head_msg_count, // approximate from head
+                id: session_id,
+                project_id,
+                project_path: Some(display_path),
+                summary,
+                message_count: cached.message_count,
                 last_modified,
-                usage: None,
+                usage: Some(cached.usage),
+                source: CLAUDE_SESSION_SOURCE.to_string(),
+                meta: Default::default(),
             });
         }
+        session_cache::flush();
 
-        // Second pass: scan for sessions not in history (older sessions without sessionId)
-        for project_entry in fs::read_dir(&projects_dir).into_iter().flatten().flatten() {
-            let project_path = project_entry.path();
-            if !project_path.is_dir() {
-                continue;
-            }
-
-            let project_id = project_path
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .to_string();
-            let display_path = decode_project_path(&project_id);
-
-            for entry in fs::read_dir(&project_path).into_iter().flatten().flatten() {
-                let path = entry.path();
-                let name = path.file_name().unwrap().to_string_lossy().to_string();
-
-                if name.ends_with(".jsonl") && !name.starts_with("agent-") {
-                    let session_id = name.trim_end_matches(".jsonl").to_string();
-
-                    // Skip if already processed from history
-                    if seen_sessions.contains(&(project_id.clone(), session_id.clone())) {
-                        continue;
-                    }
-
-                    // Read only head for summary
-                    let (summary, head_msg_count) = read_session_head(&path, 20);
-
-                    let metadata = fs::metadata(&path).ok();
-                    let last_modified = metadata
-                        .and_then(|m| m.modified().ok())
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-
-                    all_sessions.push(Session {
-                        id: session_id,
-                        project_id: project_id.clone(),
-                        project_path: Some(display_path.clone()),
-                        summary,
-                        message_count: head_msg_count,
-                        last_modified,
-                        usage: None,
-                    });
-                }
-            }
-        }
+        // Sessions of other coding agents
+        all_sessions.extend(session_sources::list_imported_sessions());
 
+        session_meta::apply(&mut all_sessions);
         all_sessions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
         Ok(all_sessions)
     })
@@ -873,8 +1345,16 @@ async fn list_all_chats(
             });
         }
 
-        // Collect all session files with metadata
+        // Collect all session and subagent files with metadata
         let mut session_files: Vec<(PathBuf, String, String, u64)> = Vec::new();
+        let file_mtime = |path: &Path| {
+            fs::metadata(path)
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        };
 
         for project_entry in fs::read_dir(&projects_dir).map_err(|e| e.to_string())? {
             let project_entry = project_entry.map_err(|e| e.to_string())?;
@@ -897,14 +1377,7 @@ async fn list_all_chats(
                 let name = path.file_name().unwrap().to_string_lossy().to_string();
 
                 if name.ends_with(".jsonl") && !name.starts_with("agent-") {
-                    let last_modified = entry
-                        .metadata()
-                        .ok()
-                        .and_then(|m| m.modified().ok())
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-
+                    let last_modified = file_mtime(&path);
                     session_files.push((
                         path,
                         project_id.clone(),
@@ -913,6 +1386,10 @@ async fn list_all_chats(
                     ));
                 }
             }
+            for path in list_subagent_files(&project_path) {
+                let last_modified = file_mtime(&path);
+                session_files.push((path, project_id.clone(), display_path.clone(), last_modified));
+            }
         }
 
         // Sort by last modified (newest first)
@@ -923,12 +1400,16 @@ async fn list_all_chats(
         // Process all sessions to get total count
         for (path, project_id, project_path, _) in session_files {
             let session_id = path.file_stem().unwrap().to_string_lossy().to_string();
-            let content = fs::read_to_string(&path).unwrap_or_default();
+            let parent_session_id = if is_subagent_file(&path) {
+                read_subagent_parent(&path)
+            } else {
+                None
+            };
 
             let mut session_summary: Option<String> = None;
             let mut session_messages: Vec<ChatMessage> = Vec::new();
 
-            for line in content.lines() {
+            let _ = for_each_complete_line(&path, 0, |line| {
                 if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
                     let line_type = parsed.line_type.as_deref();
 
@@ -953,12 +1434,14 @@ async fn list_all_chats(
                                     project_path: project_path.clone(),
                                     session_id: session_id.clone(),
                                     session_summary: None, // Will be filled later
+                                    parent_session_id: parent_session_id.clone(),
                                 });
                             }
                         }
                     }
                 }
-            }
+                true
+            });
 
             // Update session_summary for all messages
             for msg in &mut session_messages {
@@ -984,48 +1467,68 @@ async fn list_all_chats(
     .map_err(|e| e.to_string())?
 }
 
+/// Parse a session line into a displayable message
+fn parse_message_line(line: &str, line_number: usize) -> Option<Message> {
+    let parsed = serde_json::from_str::<RawLine>(line).ok()?;
+    let line_type = parsed.line_type.as_deref();
+    if line_type != Some("user") && line_type != Some("assistant") {
+        return None;
+    }
+
+    let msg = parsed.message.as_ref()?;
+    let (content, is_tool) = extract_content_with_meta(&msg.content);
+    if content.is_empty() {
+        return None;
+    }
+
+    Some(Message {
+        uuid: parsed.uuid.unwrap_or_default(),
+        role: msg.role.clone().unwrap_or_default(),
+        content,
+        timestamp: parsed.timestamp.unwrap_or_default(),
+        is_meta: parsed.is_meta.unwrap_or(false),
+        is_tool,
+        line_number,
+    })
+}
+
+/// Session of another coding agent, read in full. None for Claude Code sessions,
+/// which are read from their transcript under ~/.claude.
+fn read_imported_session(
+    source: Option<&str>,
+    session_id: &str,
+) -> Result<Option<session_sources::ImportedSession>, String> {
+    let source = match source.filter(|s| *s != CLAUDE_SESSION_SOURCE) {
+        Some(source) => session_sources::find_source(source).ok_or("Unknown session source")?,
+        None => return Ok(None),
+    };
+    session_sources::read_session_by_id(source.as_ref(), session_id).map(Some)
+}
+
 #[tauri::command]
 async fn get_session_messages(
     project_id: String,
     session_id: String,
+    source: Option<String>,
 ) -> Result<Vec<Message>, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let session_path = get_claude_dir()
-            .join("projects")
-            .join(&project_id)
-            .join(format!("{}.jsonl", session_id));
+        if let Some(imported) = read_imported_session(source.as_deref(), &session_id)? {
+            return Ok(imported.messages);
+        }
+
+        let session_path = get_session_path(&project_id, &session_id);
 
         if !session_path.exists() {
             return Err("Session not found".to_string());
         }
 
-        let content = fs::read_to_string(&session_path).map_err(|e| e.to_string())?;
         let mut messages = Vec::new();
-
-        for (idx, line) in content.lines().enumerate() {
-            if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
-                let line_type = parsed.line_type.as_deref();
-                if line_type == Some("user") || line_type == Some("assistant") {
-                    if let Some(msg) = &parsed.message {
-                        let role = msg.role.clone().unwrap_or_default();
-                        let (content, is_tool) = extract_content_with_meta(&msg.content);
-                        let is_meta = parsed.is_meta.unwrap_or(false);
-
-                        if !content.is_empty() {
-                            messages.push(Message {
-                                uuid: parsed.uuid.unwrap_or_default(),
-                                role,
-                                content,
-                                timestamp: parsed.timestamp.unwrap_or_default(),
-                                is_meta,
-                                is_tool,
-                                line_number: idx + 1,
-                            });
-                        }
-                    }
-                }
-            }
-        }
+        let mut line_number = 0;
+        for_each_complete_line(&session_path, 0, |line| {
+            line_number += 1;
+            messages.extend(parse_message_line(line, line_number));
+            true
+        })?;
 
         Ok(messages)
     })
@@ -1033,318 +1536,2164 @@ async fn get_session_messages(
     .map_err(|e| e.to_string())?
 }
 
-// ============================================================================
-// Search Feature
-// ============================================================================
+/// Lines read from the session file per step while filling a page
+const PAGE_SCAN_LINES: usize = 256;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub uuid: String,
-    pub content: String,
-    pub role: String,
-    pub project_id: String,
-    pub project_path: String,
-    pub session_id: String,
-    pub session_summary: Option<String>,
-    pub timestamp: String,
-    pub score: f32,
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageDirection {
+    #[default]
+    Forward,  // messages after the cursor
+    Backward, // messages before the cursor
 }
 
-#[tauri::command]
-async fn build_search_index() -> Result<usize, String> {
-    tauri::async_runtime::spawn_blocking(|| {
-        let index_dir = get_index_dir();
-
-        // Remove old index if exists
-        if index_dir.exists() {
-            fs::remove_dir_all(&index_dir).map_err(|e| e.to_string())?;
+#[derive(Debug, Serialize)]
+pub struct MessagesPage {
+    pub messages: Vec<Message>,   // in file order
+    pub prev_cursor: Option<usize>, // pass with "backward" for earlier messages
+    pub next_cursor: Option<usize>, // pass with "forward" for later messages
+    pub total_lines: usize,
+}
+
+/// Page through messages already in memory, as imported sessions are read in full
+fn page_loaded_messages(
+    all: Vec<Message>,
+    cursor: Option<usize>,
+    max_messages: usize,
+    direction: PageDirection,
+) -> Vec<Message> {
+    match direction {
+        PageDirection::Forward => all
+            .into_iter()
+            .filter(|m| m.line_number > cursor.unwrap_or(0))
+            .take(max_messages)
+            .collect(),
+        PageDirection::Backward => {
+            let mut before: Vec<Message> = all
+                .into_iter()
+                .filter(|m| cursor.is_none_or(|cursor| m.line_number < cursor))
+                .collect();
+            before.split_off(before.len().saturating_sub(max_messages))
         }
-        fs::create_dir_all(&index_dir).map_err(|e| e.to_string())?;
-
-        let schema = create_schema();
-        let index = Index::create_in_dir(&index_dir, schema.clone()).map_err(|e| e.to_string())?;
-
-        // Register jieba tokenizer for Chinese support
-        register_jieba_tokenizer(&index);
+    }
+}
 
-        let mut index_writer: IndexWriter = index
-            .writer(50_000_000) // 50MB heap
-            .map_err(|e| e.to_string())?;
+/// A window of session messages, read without loading the whole file.
+/// The cursor is a line number; without one, forward starts at the first message and
+/// backward at the last.
+#[tauri::command]
+async fn get_session_messages_page(
+    project_id: String,
+    session_id: String,
+    cursor: Option<usize>,
+    limit: Option<usize>,
+    direction: Option<PageDirection>,
+    source: Option<String>,
+) -> Result<MessagesPage, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let max_messages = limit.unwrap_or(50);
 
-        let uuid_field = schema.get_field("uuid").unwrap();
-        let content_field = schema.get_field("content").unwrap();
-        let role_field = schema.get_field("role").unwrap();
-        let project_id_field = schema.get_field("project_id").unwrap();
-        let project_path_field = schema.get_field("project_path").unwrap();
-        let session_id_field = schema.get_field("session_id").unwrap();
-        let session_summary_field = schema.get_field("session_summary").unwrap();
-        let timestamp_field = schema.get_field("timestamp").unwrap();
+        if let Some(imported) = read_imported_session(source.as_deref(), &session_id)? {
+            let total_lines = imported.messages.last().map(|m| m.line_number).unwrap_or(0);
+            let messages =
+                page_loaded_messages(imported.messages, cursor, max_messages, direction.unwrap_or_default());
+            return Ok(MessagesPage {
+                prev_cursor: messages.first().map(|m| m.line_number).filter(|&n| n > 1),
+                next_cursor: messages.last().map(|m| m.line_number).filter(|&n| n < total_lines),
+                messages,
+                total_lines,
+            });
+        }
 
-        let projects_dir = get_claude_dir().join("projects");
-        let mut indexed_count = 0;
+        let session_path = get_session_path(&project_id, &session_id);
+        if !session_path.exists() {
+            return Err("Session not found".to_string());
+        }
 
-        // === Command stats collection ===
-        let mut command_stats: HashMap<String, HashMap<String, usize>> = HashMap::new();
-        let command_pattern = regex::Regex::new(r"<command-name>(/[^<]+)</command-name>")
-            .map_err(|e| e.to_string())?;
+        let total_lines = session_lines::line_count(&session_path)?;
+        let mut messages = Vec::new();
 
-        // Build alias -> canonical name mapping
-        let mut alias_map: HashMap<String, String> = HashMap::new();
-        let commands_dir = get_claude_dir().join("commands");
-
-        fn scan_commands_for_aliases(dir: &std::path::Path, alias_map: &mut HashMap<String, String>, base_dir: &std::path::Path) {
-            if let Ok(entries) = fs::read_dir(dir) {
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        scan_commands_for_aliases(&path, alias_map, base_dir);
-                    } else if path.extension().map_or(false, |e| e == "md") {
-                        let rel_path = path.strip_prefix(base_dir).unwrap_or(&path);
-                        let canonical = rel_path
-                            .with_extension("")
-                            .to_string_lossy()
-                            .replace('/', ":")
-                            .replace('\\', ":");
-
-                        if let Ok(content) = fs::read_to_string(&path) {
-                            if content.starts_with("---") {
-                                if let Some(end) = content[3..].find("---") {
-                                    let fm = &content[3..3 + end];
-                                    for line in fm.lines() {
-                                        if line.starts_with("aliases:") {
-                                            let aliases_str = line.trim_start_matches("aliases:").trim();
-                                            for alias in aliases_str.split(',') {
-                                                let alias = alias.trim()
-                                                    .trim_matches('"')
-                                                    .trim_matches('\'')
-                                                    .trim_start_matches('/')
-                                                    .to_string();
-                                                if !alias.is_empty() {
-                                                    alias_map.insert(alias, canonical.clone());
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+        match direction.unwrap_or_default() {
+            PageDirection::Forward => {
+                let mut next = cursor.unwrap_or(0) + 1;
+                while messages.len() < max_messages && next <= total_lines {
+                    let lines = session_lines::read_lines(&session_path, next, PAGE_SCAN_LINES)?;
+                    if lines.is_empty() {
+                        break;
+                    }
+                    for (line_number, line) in lines {
+                        next = line_number + 1;
+                        messages.extend(parse_message_line(&line, line_number));
+                        if messages.len() == max_messages {
+                            break;
+                        }
+                    }
+                }
+            }
+            PageDirection::Backward => {
+                let mut end = cursor.unwrap_or(total_lines + 1).min(total_lines + 1);
+                while messages.len() < max_messages && end > 1 {
+                    let first = end.saturating_sub(PAGE_SCAN_LINES).max(1);
+                    let lines = session_lines::read_lines(&session_path, first, end - first)?;
+                    if lines.is_empty() {
+                        break;
+                    }
+                    for (line_number, line) in lines.into_iter().rev() {
+                        end = line_number;
+                        messages.extend(parse_message_line(&line, line_number));
+                        if messages.len() == max_messages {
+                            break;
                         }
                     }
                 }
+                messages.reverse();
             }
         }
 
-        if commands_dir.exists() {
-            scan_commands_for_aliases(&commands_dir, &mut alias_map, &commands_dir);
-        }
-        // === End command stats setup ===
+        let prev_cursor = messages
+            .first()
+            .map(|m| m.line_number)
+            .filter(|&line_number| line_number > 1);
+        let next_cursor = messages
+            .last()
+            .map(|m| m.line_number)
+            .filter(|&line_number| line_number < total_lines);
+
+        Ok(MessagesPage {
+            messages,
+            prev_cursor,
+            next_cursor,
+            total_lines,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-        if !projects_dir.exists() {
-            return Ok(0);
+/// Turn a user or assistant line into a transcript message
+fn parse_transcript_message(parsed: RawLine, line_number: usize) -> Option<TranscriptMessage> {
+    let line_type = parsed.line_type.as_deref();
+    if line_type != Some("user") && line_type != Some("assistant") {
+        return None;
+    }
+
+    let msg = parsed.message?;
+    let blocks = msg.content.as_ref().map(parse_content_blocks).unwrap_or_default();
+    if blocks.is_empty() {
+        return None;
+    }
+
+    Some(TranscriptMessage {
+        uuid: parsed.uuid.unwrap_or_default(),
+        parent_uuid: parsed.parent_uuid,
+        is_sidechain: parsed.is_sidechain.unwrap_or(false),
+        role: msg.role.unwrap_or_default(),
+        timestamp: parsed.timestamp.unwrap_or_default(),
+        is_meta: parsed.is_meta.unwrap_or(false),
+        line_number,
+        blocks,
+    })
+}
+
+fn read_session_transcript(path: &Path) -> Result<Vec<TranscriptMessage>, String> {
+    let mut messages = Vec::new();
+    let mut line_number = 0;
+    for_each_complete_line(path, 0, |line| {
+        line_number += 1;
+        if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
+            messages.extend(parse_transcript_message(parsed, line_number));
         }
+        true
+    })?;
 
-        for project_entry in fs::read_dir(&projects_dir).map_err(|e| e.to_string())? {
-            let project_entry = project_entry.map_err(|e| e.to_string())?;
-            let project_path_buf = project_entry.path();
+    link_tool_blocks(&mut messages);
+    Ok(messages)
+}
 
-            if !project_path_buf.is_dir() {
-                continue;
+/// Transcript of an imported session. Other agents' messages are kept as plain text.
+fn imported_transcript(messages: Vec<Message>) -> Vec<TranscriptMessage> {
+    messages
+        .into_iter()
+        .map(|m| TranscriptMessage {
+            uuid: m.uuid,
+            parent_uuid: None,
+            is_sidechain: false,
+            role: m.role,
+            timestamp: m.timestamp,
+            is_meta: m.is_meta,
+            line_number: m.line_number,
+            blocks: vec![ContentBlock::Text { text: m.content }],
+        })
+        .collect()
+}
+
+/// Every message of a session with typed content blocks, tool results linked to their calls
+#[tauri::command]
+async fn get_session_transcript(
+    project_id: String,
+    session_id: String,
+    source: Option<String>,
+) -> Result<Vec<TranscriptMessage>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(imported) = read_imported_session(source.as_deref(), &session_id)? {
+            return Ok(imported_transcript(imported.messages));
+        }
+        let session_path = get_session_path(&project_id, &session_id);
+        if !session_path.exists() {
+            return Err("Session not found".to_string());
+        }
+        read_session_transcript(&session_path)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Render a session for sharing as Markdown, standalone HTML or normalized JSON
+#[tauri::command]
+async fn export_session(
+    project_id: String,
+    session_id: String,
+    format: session_export::ExportFormat,
+    options: Option<session_export::ExportOptions>,
+    source: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (messages, summary, project_path) = match read_imported_session(source.as_deref(), &session_id)? {
+            Some(imported) => {
+                let project_path = imported
+                    .session
+                    .project_path
+                    .unwrap_or_else(|| decode_project_path(&project_id));
+                (imported_transcript(imported.messages), imported.session.summary, project_path)
+            }
+            None => {
+                let session_path = get_session_path(&project_id, &session_id);
+                if !session_path.exists() {
+                    return Err("Session not found".to_string());
+                }
+                let (summary, _) = read_session_head(&session_path, 20);
+                (read_session_transcript(&session_path)?, summary, decode_project_path(&project_id))
             }
+        };
+        let info = session_export::SessionInfo {
+            session_id: &session_id,
+            project_id: &project_id,
+            project_path: &project_path,
+            summary: summary.as_deref(),
+        };
 
-            let project_id = project_path_buf.file_name().unwrap().to_string_lossy().to_string();
-            let display_path = decode_project_path(&project_id);
+        session_export::export(&info, messages, format, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-            for entry in fs::read_dir(&project_path_buf).map_err(|e| e.to_string())? {
-                let entry = entry.map_err(|e| e.to_string())?;
-                let path = entry.path();
-                let name = path.file_name().unwrap().to_string_lossy().to_string();
+// ============================================================================
+// Conversation Tree
+// ============================================================================
 
-                if name.ends_with(".jsonl") && !name.starts_with("agent-") {
-                    let session_id = name.trim_end_matches(".jsonl").to_string();
-                    let file_content = fs::read_to_string(&path).unwrap_or_default();
+/// Length of the text preview on tree nodes
+const TREE_PREVIEW_CHARS: usize = 120;
 
-                    let mut session_summary: Option<String> = None;
+#[derive(Debug, Serialize)]
+pub struct TreeNode {
+    pub uuid: String,
+    pub parent_uuid: Option<String>, // None for roots, also when the parent is not in this file
+    pub line_type: String,
+    pub role: Option<String>,
+    pub timestamp: String,
+    pub line_number: usize,
+    pub is_sidechain: bool,
+    pub preview: String,
+    pub children: Vec<String>, // in file order
+    pub is_active: bool,       // on the path to the active leaf
+}
 
-                    // First pass: get summary
-                    for line in file_content.lines() {
-                        if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
-                            if parsed.line_type.as_deref() == Some("summary") {
-                                session_summary = parsed.summary;
-                                break;
-                            }
-                        }
-                    }
+#[derive(Debug, Serialize)]
+pub struct ConversationBranch {
+    pub leaf_uuid: String,
+    pub fork_uuid: Option<String>, // nearest ancestor with more than one child
+    pub depth: usize,              // nodes from the root to the leaf
+    pub timestamp: String,         // of the leaf
+    pub is_active: bool,
+    pub is_sidechain: bool,
+}
 
-                    // Second pass: index messages + collect command stats
-                    for line in file_content.lines() {
-                        if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
-                            let line_type = parsed.line_type.as_deref();
-
-                            if line_type == Some("user") || line_type == Some("assistant") {
-                                if let Some(msg) = &parsed.message {
-                                    let role = msg.role.clone().unwrap_or_default();
-                                    let (text_content, _) = extract_content_with_meta(&msg.content);
-                                    let is_meta = parsed.is_meta.unwrap_or(false);
-
-                                    if !is_meta && !text_content.is_empty() {
-                                        index_writer.add_document(doc!(
-                                            uuid_field => parsed.uuid.clone().unwrap_or_default(),
-                                            content_field => text_content,
-                                            role_field => role,
-                                            project_id_field => project_id.clone(),
-                                            project_path_field => display_path.clone(),
-                                            session_id_field => session_id.clone(),
-                                            session_summary_field => session_summary.clone().unwrap_or_default(),
-                                            timestamp_field => parsed.timestamp.clone().unwrap_or_default(),
-                                        )).map_err(|e| e.to_string())?;
-
-                                        indexed_count += 1;
-                                    }
-                                }
-                            }
+#[derive(Debug, Serialize)]
+pub struct ConversationTree {
+    pub nodes: Vec<TreeNode>, // in file order
+    pub roots: Vec<String>,
+    pub branches: Vec<ConversationBranch>, // one per leaf, inactive ones are abandoned forks
+    pub active_leaf: Option<String>,
+}
+
+/// Indices from the root down to `leaf`
+fn tree_path(nodes: &[TreeNode], positions: &HashMap<String, usize>, leaf: usize) -> Vec<usize> {
+    let mut path = vec![leaf];
+    let mut visited = std::collections::HashSet::from([leaf]);
+    let mut current = leaf;
+    while let Some(parent) = nodes[current].parent_uuid.as_ref().and_then(|p| positions.get(p)) {
+        if !visited.insert(*parent) {
+            break; // malformed file with a cycle
+        }
+        path.push(*parent);
+        current = *parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Rebuild the parentUuid DAG of a session file.
+/// The active leaf is the most recently written leaf of the main (non-sidechain) thread,
+/// i.e. where the conversation continued after any rewind.
+fn build_conversation_tree(path: &Path) -> Result<(ConversationTree, HashMap<String, usize>), String> {
+    let mut nodes: Vec<TreeNode> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut line_number = 0;
+
+    for_each_complete_line(path, 0, |line| {
+        line_number += 1;
+        let parsed = match serde_json::from_str::<RawLine>(line) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+        let uuid = match parsed.uuid {
+            Some(uuid) if !positions.contains_key(&uuid) => uuid,
+            _ => return true,
+        };
+        let (role, preview) = match &parsed.message {
+            Some(msg) => {
+                let (text, _) = extract_content_with_meta(&msg.content);
+                (msg.role.clone(), text.chars().take(TREE_PREVIEW_CHARS).collect())
+            }
+            None => (None, String::new()),
+        };
 
-                            // Collect command stats from any line containing <command-name>
-                            // Skip queue-operation entries (internal logs, not actual command invocations)
-                            if line.contains("<command-name>") && !line.contains("\"type\":\"queue-operation\"") {
-                                if let Some(ts_str) = &parsed.timestamp {
-                                    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(ts_str) {
-                                        let week_key = ts.format("%Y-W%V").to_string();
-                                        for cap in command_pattern.captures_iter(line) {
-                                            if let Some(cmd_match) = cap.get(1) {
-                                                let raw_name = cmd_match.as_str().trim_start_matches('/').to_string();
-                                                let name = alias_map.get(&raw_name).cloned().unwrap_or(raw_name);
-                                                command_stats
-                                                    .entry(name)
-                                                    .or_default()
-                                                    .entry(week_key.clone())
-                                                    .and_modify(|c| *c += 1)
-                                                    .or_insert(1);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        positions.insert(uuid.clone(), nodes.len());
+        nodes.push(TreeNode {
+            uuid,
+            parent_uuid: parsed.parent_uuid,
+            line_type: parsed.line_type.unwrap_or_default(),
+            role,
+            timestamp: parsed.timestamp.unwrap_or_default(),
+            line_number,
+            is_sidechain: parsed.is_sidechain.unwrap_or(false),
+            preview,
+            children: Vec::new(),
+            is_active: false,
+        });
+        true
+    })?;
+
+    // Link children; parents missing from the file make their children roots
+    let mut roots = Vec::new();
+    for i in 0..nodes.len() {
+        match nodes[i].parent_uuid.as_ref().and_then(|p| positions.get(p)).copied() {
+            Some(parent) => {
+                let uuid = nodes[i].uuid.clone();
+                nodes[parent].children.push(uuid);
+            }
+            None => {
+                nodes[i].parent_uuid = None;
+                roots.push(nodes[i].uuid.clone());
             }
         }
+    }
 
-        index_writer.commit().map_err(|e| e.to_string())?;
+    let leaves: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].children.is_empty()).collect();
+    let active_leaf = leaves
+        .iter()
+        .copied()
+        .filter(|&i| !nodes[i].is_sidechain)
+        .max_by_key(|&i| nodes[i].line_number)
+        .or_else(|| leaves.iter().copied().max_by_key(|&i| nodes[i].line_number));
+
+    if let Some(leaf) = active_leaf {
+        for i in tree_path(&nodes, &positions, leaf) {
+            nodes[i].is_active = true;
+        }
+    }
 
-        // Store search index in global state
-        let mut guard = SEARCH_INDEX.lock().map_err(|e| e.to_string())?;
-        *guard = Some(SearchIndex { index, schema });
+    let branches = leaves
+        .iter()
+        .map(|&leaf| {
+            let path = tree_path(&nodes, &positions, leaf);
+            let fork_uuid = path
+                .iter()
+                .rev()
+                .skip(1)
+                .find(|&&i| nodes[i].children.len() > 1)
+                .map(|&i| nodes[i].uuid.clone());
+            ConversationBranch {
+                leaf_uuid: nodes[leaf].uuid.clone(),
+                fork_uuid,
+                depth: path.len(),
+                timestamp: nodes[leaf].timestamp.clone(),
+                is_active: Some(leaf) == active_leaf,
+                is_sidechain: nodes[leaf].is_sidechain,
+            }
+        })
+        .collect();
 
-        // Write command stats to file
-        let stats_path = get_command_stats_path();
-        if let Some(parent) = stats_path.parent() {
-            fs::create_dir_all(parent).ok();
-        }
-        let stats_json = serde_json::json!({
-            "updated_at": chrono::Utc::now().timestamp(),
-            "commands": command_stats,
-        });
-        fs::write(&stats_path, serde_json::to_string_pretty(&stats_json).unwrap_or_default()).ok();
+    let tree = ConversationTree {
+        active_leaf: active_leaf.map(|i| nodes[i].uuid.clone()),
+        nodes,
+        roots,
+        branches,
+    };
+    Ok((tree, positions))
+}
 
-        Ok(indexed_count)
+/// The parentUuid tree of a session, with its branches and active leaf
+#[tauri::command]
+async fn get_conversation_tree(project_id: String, session_id: String) -> Result<ConversationTree, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let session_path = get_session_path(&project_id, &session_id);
+        if !session_path.exists() {
+            return Err("Session not found".to_string());
+        }
+        Ok(build_conversation_tree(&session_path)?.0)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Messages on the path from the root to `leaf_uuid` (the active leaf when omitted)
+#[tauri::command]
+async fn get_conversation_branch(
+    project_id: String,
+    session_id: String,
+    leaf_uuid: Option<String>,
+) -> Result<Vec<TranscriptMessage>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let session_path = get_session_path(&project_id, &session_id);
+        if !session_path.exists() {
+            return Err("Session not found".to_string());
+        }
+
+        let (tree, positions) = build_conversation_tree(&session_path)?;
+        let leaf = match leaf_uuid.or(tree.active_leaf) {
+            Some(uuid) => *positions.get(&uuid).ok_or("Message not found in session")?,
+            None => return Ok(Vec::new()),
+        };
+        let path_lines: std::collections::HashSet<usize> = tree_path(&tree.nodes, &positions, leaf)
+            .into_iter()
+            .map(|i| tree.nodes[i].line_number)
+            .collect();
+
+        let mut messages = Vec::new();
+        let mut line_number = 0;
+        for_each_complete_line(&session_path, 0, |line| {
+            line_number += 1;
+            if path_lines.contains(&line_number) {
+                if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
+                    messages.extend(parse_transcript_message(parsed, line_number));
+                }
+            }
+            true
+        })?;
+
+        link_tool_blocks(&mut messages);
+        Ok(messages)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// ============================================================================
+// Search Feature
+// ============================================================================
+
+/// Highlighted part of a snippet, in UTF-16 code units to match JS string indices
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HighlightRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchSnippet {
+    pub fragment: String,
+    pub highlights: Vec<HighlightRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub uuid: String,
+    pub content: String,
+    pub snippet: SearchSnippet,
+    pub line_number: usize, // line in the session file, as in get_session_messages
+    pub role: String,
+    pub project_id: String,
+    pub project_path: String,
+    pub session_id: String,
+    pub session_summary: Option<String>,
+    pub timestamp: String,
+    pub score: f32,
+    pub is_subagent: bool,
+    pub parent_session_id: Option<String>, // session that spawned the subagent
+    pub source: String,
+    pub tags: Vec<String>,
+}
+
+/// Per-file state of the search index, persisted next to the index so that
+/// `build_search_index` only reads what changed since the previous run
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct IndexManifest {
+    files: HashMap<String, IndexedFile>, // session file path -> state
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+struct IndexedFile {
+    project_id: String,
+    session_id: String,
+    size: u64,
+    mtime: u64,
+    /// Byte offset just past the last line that has been indexed
+    offset: u64,
+    /// Number of lines before `offset`, to keep line numbers of appended messages
+    lines: u64,
+    /// Hash of the first line, used to detect files rewritten in place
+    head_hash: u64,
+    summary: Option<String>,
+    /// Session tags written on every message, a change means indexing the file again
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Raw command name -> week -> count, collected from the indexed lines
+    commands: HashMap<String, HashMap<String, usize>>,
+}
+
+fn get_index_manifest_path() -> PathBuf {
+    get_index_dir().join("manifest.json")
+}
+
+fn load_index_manifest() -> Option<IndexManifest> {
+    let content = fs::read_to_string(get_index_manifest_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_index_manifest(manifest: &IndexManifest) -> Result<(), String> {
+    let path = get_index_manifest_path();
+    let tmp_path = path.with_extension("json.tmp");
+    let output = serde_json::to_string(manifest).map_err(|e| e.to_string())?;
+    fs::write(&tmp_path, output).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+}
+
+/// Schema fields written for each indexed message
+struct SearchFields {
+    uuid: Field,
+    content: Field,
+    role: Field,
+    project_id: Field,
+    project_path: Field,
+    session_id: Field,
+    session_summary: Field,
+    timestamp: Field,
+    is_tool: Field,
+    line_number: Field,
+    is_subagent: Field,
+    parent_session_id: Field,
+    source: Field,
+    tags: Field,
+    tool_name: Field,
+    tool_input: Field,
+    file_paths: Field,
+    tool_output: Field,
+}
+
+impl SearchFields {
+    fn new(schema: &Schema) -> Self {
+        Self {
+            uuid: schema.get_field("uuid").unwrap(),
+            content: schema.get_field("content").unwrap(),
+            role: schema.get_field("role").unwrap(),
+            project_id: schema.get_field("project_id").unwrap(),
+            project_path: schema.get_field("project_path").unwrap(),
+            session_id: schema.get_field("session_id").unwrap(),
+            session_summary: schema.get_field("session_summary").unwrap(),
+            timestamp: schema.get_field("timestamp").unwrap(),
+            is_tool: schema.get_field("is_tool").unwrap(),
+            line_number: schema.get_field("line_number").unwrap(),
+            is_subagent: schema.get_field("is_subagent").unwrap(),
+            parent_session_id: schema.get_field("parent_session_id").unwrap(),
+            source: schema.get_field("source").unwrap(),
+            tags: schema.get_field("tags").unwrap(),
+            tool_name: schema.get_field("tool_name").unwrap(),
+            tool_input: schema.get_field("tool_input").unwrap(),
+            file_paths: schema.get_field("file_paths").unwrap(),
+            tool_output: schema.get_field("tool_output").unwrap(),
+        }
+    }
+}
+
+/// Delete every indexed message of a session
+fn delete_session_documents(
+    writer: &IndexWriter,
+    fields: &SearchFields,
+    project_id: &str,
+    session_id: &str,
+) -> Result<(), String> {
+    use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+
+    let term_query = |field: Field, value: &str| -> Box<dyn Query> {
+        Box::new(TermQuery::new(
+            Term::from_field_text(field, value),
+            IndexRecordOption::Basic,
+        ))
+    };
+    let query = BooleanQuery::new(vec![
+        (Occur::Must, term_query(fields.project_id, project_id)),
+        (Occur::Must, term_query(fields.session_id, session_id)),
+    ]);
+    writer
+        .delete_query(Box::new(query))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Call `f` for every complete line starting at byte `offset`, stopping early if it returns false.
+/// A trailing line without newline is only treated as complete if it is valid JSON,
+/// so a line Claude Code is still writing gets picked up by the next sync.
+/// Returns the offset just past the last line handed to `f`.
+fn for_each_complete_line(
+    path: &Path,
+    offset: u64,
+    mut f: impl FnMut(&str) -> bool,
+) -> Result<u64, String> {
+    use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file);
+    let mut pos = offset;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        let complete = buf.ends_with(b"\n");
+        let text = String::from_utf8_lossy(&buf);
+        let line = text.trim_end();
+        if !complete && serde_json::from_str::<Value>(line).is_err() {
+            break;
+        }
+        pos += read as u64;
+        if !f(line) {
+            break;
+        }
+    }
+
+    Ok(pos)
+}
+
+/// Hash of the first line of a file (0 if the file is empty or unreadable)
+fn hash_first_line(path: &Path) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut first_line = None;
+    let _ = for_each_complete_line(path, 0, |line| {
+        first_line = Some(line.to_string());
+        false
+    });
+    first_line
+        .map(|line| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            line.hash(&mut hasher);
+            hasher.finish()
+        })
+        .unwrap_or(0)
+}
+
+/// Find the first summary line at or after `offset`
+fn find_session_summary(path: &Path, offset: u64) -> Option<String> {
+    let mut summary = None;
+    let _ = for_each_complete_line(path, offset, |line| {
+        if let Ok(parsed) = serde_json::from_str::<RawLine>(line) {
+            if parsed.line_type.as_deref() == Some("summary") {
+                summary = parsed.summary;
+                return false;
+            }
+        }
+        true
+    });
+    summary
+}
+
+/// Size and modification time (ms) of a file, to detect changes since the last sync
+fn file_size_and_mtime(path: &Path) -> Result<(u64, u64), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Ok((metadata.len(), mtime))
+}
+
+/// Outcome of syncing one session file into the index
+struct SessionSync {
+    entry: IndexedFile,
+    added: usize,
+    /// The file was read from the start (new, or rewritten in place)
+    restarted: bool,
+    /// Newly indexed message texts, for embedding
+    messages: Vec<semantic_index::MessageText>,
+}
+
+/// Bring one session file up to date in the index.
+/// New lines are appended from the stored offset; truncated or rewritten files have
+/// their documents deleted and are indexed from the start.
+/// Returns the updated manifest entry and the number of documents added.
+fn sync_session_file(
+    writer: &IndexWriter,
+    fields: &SearchFields,
+    command_pattern: &regex::Regex,
+    path: &Path,
+    project_id: &str,
+    project_path: &str,
+    previous: Option<IndexedFile>,
+) -> Result<SessionSync, String> {
+    let (size, mtime) = file_size_and_mtime(path)?;
+    let session_id = path.file_stem().unwrap().to_string_lossy().to_string();
+    let is_subagent = is_subagent_file(path);
+    let parent_session_id = if is_subagent {
+        read_subagent_parent(path)
+    } else {
+        None
+    };
+    // Subagents carry the tags of the session that spawned them
+    let tags = session_meta::get(project_id, parent_session_id.as_deref().unwrap_or(&session_id)).tags;
+
+    if let Some(prev) = &previous {
+        if prev.size == size && prev.mtime == mtime && prev.tags == tags {
+            return Ok(SessionSync {
+                entry: prev.clone(),
+                added: 0,
+                restarted: false,
+                messages: Vec::new(),
+            });
+        }
+    }
+
+    let head_hash = hash_first_line(path);
+
+    // Append only if the already indexed prefix is unchanged. A summary showing up for a
+    // session that had none, or changed tags, also force a full pass, since both are
+    // stored on every message.
+    let appendable = previous.as_ref().is_some_and(|prev| {
+        size >= prev.offset
+            && prev.head_hash == head_hash
+            && prev.tags == tags
+            && (prev.summary.is_some() || find_session_summary(path, prev.offset).is_none())
+    });
+
+    let restarted = !appendable;
+    let mut entry = match previous {
+        Some(prev) if appendable => prev,
+        prev => {
+            if prev.is_some() {
+                delete_session_documents(writer, fields, project_id, &session_id)?;
+            }
+            IndexedFile {
+                project_id: project_id.to_string(),
+                session_id: session_id.clone(),
+                head_hash,
+                summary: find_session_summary(path, 0),
+                tags: tags.clone(),
+                ..Default::default()
+            }
+        }
+    };
+
+    let mut added = 0;
+    let mut messages = Vec::new();
+    let mut error = None;
+    let mut line_number = entry.lines;
+    let summary = entry.summary.clone().unwrap_or_default();
+    let commands = &mut entry.commands;
+
+    let offset = for_each_complete_line(path, entry.offset, |line| {
+        line_number += 1;
+        let parsed = match serde_json::from_str::<RawLine>(line) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+        let line_type = parsed.line_type.as_deref();
+
+        if line_type == Some("user") || line_type == Some("assistant") {
+            if let Some(msg) = &parsed.message {
+                let role = msg.role.clone().unwrap_or_default();
+                let (text_content, is_tool) = extract_content_with_meta(&msg.content);
+                let tools = extract_tool_activity(&msg.content);
+                let is_meta = parsed.is_meta.unwrap_or(false);
+
+                if !is_meta && (!text_content.is_empty() || !tools.is_empty()) {
+                    let uuid = parsed.uuid.clone().unwrap_or_default();
+                    if !text_content.is_empty() {
+                        messages.push(semantic_index::MessageText {
+                            uuid: uuid.clone(),
+                            text: text_content.clone(),
+                        });
+                    }
+                    let mut document = doc!(
+                        fields.uuid => uuid,
+                        fields.content => text_content,
+                        fields.role => role,
+                        fields.project_id => project_id.to_string(),
+                        fields.project_path => project_path.to_string(),
+                        fields.session_id => session_id.clone(),
+                        fields.session_summary => summary.clone(),
+                        fields.is_tool => is_tool,
+                        fields.line_number => line_number,
+                        fields.is_subagent => is_subagent,
+                        fields.source => CLAUDE_SESSION_SOURCE,
+                    );
+                    if let Some(parent) = &parent_session_id {
+                        document.add_text(fields.parent_session_id, parent);
+                    }
+                    for tag in &tags {
+                        document.add_facet(fields.tags, Facet::from_path([tag.as_str()]));
+                    }
+                    if let Some(ts) = parsed.timestamp.as_deref().and_then(parse_search_timestamp) {
+                        document.add_date(fields.timestamp, ts);
+                    }
+                    for name in &tools.names {
+                        document.add_text(fields.tool_name, name);
+                    }
+                    for file_path in &tools.file_paths {
+                        document.add_text(fields.file_paths, file_path);
+                    }
+                    if !tools.inputs.is_empty() {
+                        document.add_text(fields.tool_input, tools.inputs.join("\n"));
+                    }
+                    if !tools.outputs.is_empty() {
+                        document.add_text(fields.tool_output, tools.outputs.join("\n"));
+                    }
+                    if let Err(e) = writer.add_document(document) {
+                        error = Some(e.to_string());
+                        return false;
+                    }
+                    added += 1;
+                }
+            }
+        }
+
+        // Collect command stats from any line containing <command-name>
+        // Skip queue-operation entries (internal logs, not actual command invocations)
+        if line.contains("<command-name>") && !line.contains("\"type\":\"queue-operation\"") {
+            if let Some(ts_str) = &parsed.timestamp {
+                if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(ts_str) {
+                    let week_key = ts.format("%Y-W%V").to_string();
+                    for cap in command_pattern.captures_iter(line) {
+                        if let Some(cmd_match) = cap.get(1) {
+                            let raw_name = cmd_match.as_str().trim_start_matches('/').to_string();
+                            *commands
+                                .entry(raw_name)
+                                .or_default()
+                                .entry(week_key.clone())
+                                .or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        true
+    })?;
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    entry.offset = offset;
+    entry.lines = line_number;
+    entry.size = size;
+    entry.mtime = mtime;
+    Ok(SessionSync {
+        entry,
+        added,
+        restarted,
+        messages,
+    })
+}
+
+/// Change to the vector index, queued while the search index is updated
+enum EmbedTask {
+    Add {
+        project_id: String,
+        session_id: String,
+        /// Replace the session's vectors instead of appending to them
+        restarted: bool,
+        messages: Vec<semantic_index::MessageText>,
+    },
+    Remove {
+        project_id: String,
+        session_id: String,
+    },
+}
+
+/// Embedding runs on its own thread, in the order tasks were queued, so it never holds
+/// `SEARCH_INDEX_MANIFEST` and the syncs waiting on it
+static EMBED_QUEUE: LazyLock<Sender<EmbedTask>> = LazyLock::new(|| {
+    let (tx, rx) = channel::<EmbedTask>();
+    std::thread::spawn(move || {
+        for task in rx {
+            match task {
+                EmbedTask::Add {
+                    project_id,
+                    session_id,
+                    restarted,
+                    messages,
+                } => {
+                    if restarted {
+                        semantic_index::remove_session(&project_id, &session_id);
+                    }
+                    if !messages.is_empty() {
+                        if let Err(e) = semantic_index::add_messages(&project_id, &session_id, &messages) {
+                            eprintln!("Failed to embed session {}: {}", session_id, e);
+                        }
+                    }
+                }
+                EmbedTask::Remove { project_id, session_id } => {
+                    semantic_index::remove_session(&project_id, &session_id)
+                }
+            }
+        }
+    });
+    tx
+});
+
+fn queue_embed_task(task: EmbedTask) {
+    if EMBED_QUEUE.send(task).is_err() {
+        eprintln!("Failed to queue embedding: the embedding thread has stopped");
+    }
+}
+
+/// Queue embedding of the messages a sync added, replacing the session's vectors if it
+/// was re-read from the start
+fn embed_session_messages(sync: &mut SessionSync) {
+    if !sync.restarted && sync.messages.is_empty() {
+        return;
+    }
+    queue_embed_task(EmbedTask::Add {
+        project_id: sync.entry.project_id.clone(),
+        session_id: sync.entry.session_id.clone(),
+        restarted: sync.restarted,
+        messages: std::mem::take(&mut sync.messages),
+    });
+}
+
+/// Index a session of another coding agent. Their transcripts are not append-only,
+/// so a changed file is indexed again from scratch. Returns None when the file cannot
+/// be read; its previous documents are kept and it is retried on the next sync.
+fn sync_imported_session_file(
+    writer: &IndexWriter,
+    fields: &SearchFields,
+    source: &dyn session_sources::SessionSource,
+    path: &Path,
+    previous: Option<&IndexedFile>,
+) -> Result<Option<SessionSync>, String> {
+    let (size, mtime) = file_size_and_mtime(path)?;
+    // Imported sessions are grouped under their cwd's project, which is only known after
+    // parsing, so the tags are looked up under the previously indexed IDs
+    let previous_tags = previous
+        .map(|p| session_meta::get(&p.project_id, &p.session_id).tags)
+        .unwrap_or_default();
+    if let Some(prev) = previous.filter(|p| p.size == size && p.mtime == mtime && p.tags == previous_tags) {
+        return Ok(Some(SessionSync {
+            entry: prev.clone(),
+            added: 0,
+            restarted: false,
+            messages: Vec::new(),
+        }));
+    }
+
+    let imported = match source.read_session(path) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("Failed to read {} session {}: {}", source.name(), path.display(), e);
+            return Ok(None);
+        }
+    };
+    if let Some(prev) = previous {
+        delete_session_documents(writer, fields, &prev.project_id, &prev.session_id)?;
+    }
+
+    let session = &imported.session;
+    let summary = session.summary.clone().unwrap_or_default();
+    let tags = session_meta::get(&session.project_id, &session.id).tags;
+    let mut messages = Vec::new();
+    for message in &imported.messages {
+        let mut document = doc!(
+            fields.uuid => message.uuid.clone(),
+            fields.content => message.content.clone(),
+            fields.role => message.role.clone(),
+            fields.project_id => session.project_id.clone(),
+            fields.project_path => session.project_path.clone().unwrap_or_default(),
+            fields.session_id => session.id.clone(),
+            fields.session_summary => summary.clone(),
+            fields.is_tool => message.is_tool,
+            fields.line_number => message.line_number as u64,
+            fields.is_subagent => false,
+            fields.source => source.name(),
+        );
+        for tag in &tags {
+            document.add_facet(fields.tags, Facet::from_path([tag.as_str()]));
+        }
+        if let Some(ts) = parse_search_timestamp(&message.timestamp) {
+            document.add_date(fields.timestamp, ts);
+        }
+        writer.add_document(document).map_err(|e| e.to_string())?;
+        if !message.is_tool {
+            messages.push(semantic_index::MessageText {
+                uuid: message.uuid.clone(),
+                text: message.content.clone(),
+            });
+        }
+    }
+
+    Ok(Some(SessionSync {
+        entry: IndexedFile {
+            project_id: session.project_id.clone(),
+            session_id: session.id.clone(),
+            size,
+            mtime,
+            offset: size,
+            lines: imported.messages.len() as u64,
+            summary: session.summary.clone(),
+            tags,
+            ..Default::default()
+        },
+        added: imported.messages.len(),
+        restarted: true,
+        messages,
+    }))
+}
+
+/// Incrementally sync the search index with ~/.claude/projects.
+/// Returns the number of newly indexed messages.
+#[tauri::command]
+async fn build_search_index() -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(sync_search_index)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn sync_search_index() -> Result<usize, String> {
+    let mut manifest_guard = SEARCH_INDEX_MANIFEST.lock().map_err(|e| e.to_string())?;
+    let index_dir = get_index_dir();
+    let schema = create_schema();
+
+    // Reuse the existing index when its manifest is readable and its schema version is
+    // current, otherwise start over. Vectors from another embedding model also need every
+    // session read again.
+    let embed = semantic_index::is_enabled();
+    let reembed = embed && semantic_index::needs_rebuild();
+    let current_version = read_schema_version() == Some(SEARCH_SCHEMA_VERSION);
+    let existing = match manifest_guard.take().or_else(load_index_manifest) {
+        Some(manifest) if current_version && !reembed => Index::open_in_dir(&index_dir)
+            .ok()
+            .filter(|index| index.schema() == schema)
+            .map(|index| (index, manifest)),
+        _ => None,
+    };
+    let (index, mut manifest) = match existing {
+        Some(existing) => existing,
+        None => {
+            // Release the loaded index before its files are removed
+            *SEARCH_INDEX.lock().map_err(|e| e.to_string())? = None;
+            if index_dir.exists() {
+                fs::remove_dir_all(&index_dir).map_err(|e| e.to_string())?;
+            }
+            fs::create_dir_all(&index_dir).map_err(|e| e.to_string())?;
+            let index = Index::create_in_dir(&index_dir, schema.clone())
+                .map_err(|e| e.to_string())?;
+            fs::write(get_schema_version_path(), SEARCH_SCHEMA_VERSION.to_string())
+                .map_err(|e| e.to_string())?;
+            semantic_index::clear()?;
+            (index, IndexManifest::default())
+        }
+    };
+
+    // Register jieba tokenizer for Chinese support
+    register_jieba_tokenizer(&index);
+
+    let mut index_writer: IndexWriter = index
+        .writer(50_000_000) // 50MB heap
+        .map_err(|e| e.to_string())?;
+
+    let fields = SearchFields::new(&schema);
+    let projects_dir = get_claude_dir().join("projects");
+    let mut indexed_count = 0;
+
+    // === Command stats collection ===
+    let command_pattern = regex::Regex::new(r"<command-name>(/[^<]+)</command-name>")
+        .map_err(|e| e.to_string())?;
+
+    // Build alias -> canonical name mapping
+    let mut alias_map: HashMap<String, String> = HashMap::new();
+    let commands_dir = get_claude_dir().join("commands");
+
+    fn scan_commands_for_aliases(dir: &std::path::Path, alias_map: &mut HashMap<String, String>, base_dir: &std::path::Path) {
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if path.is_dir() {
+                    scan_commands_for_aliases(&path, alias_map, base_dir);
+                } else if path.extension().map_or(false, |e| e == "md") {
+                    let rel_path = path.strip_prefix(base_dir).unwrap_or(&path);
+                    let canonical = rel_path
+                        .with_extension("")
+                        .to_string_lossy()
+                        .replace('/', ":")
+                        .replace('\\', ":");
+
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        if content.starts_with("---") {
+                            if let Some(end) = content[3..].find("---") {
+                                let fm = &content[3..3 + end];
+                                for line in fm.lines() {
+                                    if line.starts_with("aliases:") {
+                                        let aliases_str = line.trim_start_matches("aliases:").trim();
+                                        for alias in aliases_str.split(',') {
+                                            let alias = alias.trim()
+                                                .trim_matches('"')
+                                                .trim_matches('\'')
+                                                .trim_start_matches('/')
+                                                .to_string();
+                                            if !alias.is_empty() {
+                                                alias_map.insert(alias, canonical.clone());
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if commands_dir.exists() {
+        scan_commands_for_aliases(&commands_dir, &mut alias_map, &commands_dir);
+    }
+    // === End command stats setup ===
+
+    let mut seen_files: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    if projects_dir.exists() {
+        for project_entry in fs::read_dir(&projects_dir).map_err(|e| e.to_string())? {
+            let project_entry = project_entry.map_err(|e| e.to_string())?;
+            let project_path_buf = project_entry.path();
+
+            if !project_path_buf.is_dir() {
+                continue;
+            }
+
+            let project_id = project_path_buf.file_name().unwrap().to_string_lossy().to_string();
+            let display_path = decode_project_path(&project_id);
+
+            // Sessions and their subagent transcripts
+            let mut session_files = Vec::new();
+            for entry in fs::read_dir(&project_path_buf).map_err(|e| e.to_string())? {
+                let path = entry.map_err(|e| e.to_string())?.path();
+                if path.extension().is_some_and(|e| e == "jsonl") {
+                    session_files.push(path);
+                }
+            }
+            session_files.extend(list_nested_subagent_files(&project_path_buf));
+
+            for path in session_files {
+                let key = path.to_string_lossy().to_string();
+                let previous = manifest.files.remove(&key);
+                let mut sync = sync_session_file(
+                    &index_writer,
+                    &fields,
+                    &command_pattern,
+                    &path,
+                    &project_id,
+                    &display_path,
+                    previous,
+                )?;
+                if embed {
+                    embed_session_messages(&mut sync);
+                }
+                indexed_count += sync.added;
+                manifest.files.insert(key.clone(), sync.entry);
+                seen_files.insert(key);
+            }
+        }
+    }
+
+    // Sessions of other coding agents
+    for source in session_sources::all_sources() {
+        for path in source.session_files() {
+            let key = path.to_string_lossy().to_string();
+            let previous = manifest.files.get(&key);
+            if let Some(mut sync) = sync_imported_session_file(&index_writer, &fields, source.as_ref(), &path, previous)? {
+                if embed {
+                    embed_session_messages(&mut sync);
+                }
+                indexed_count += sync.added;
+                manifest.files.insert(key.clone(), sync.entry);
+            }
+            seen_files.insert(key);
+        }
+    }
+
+    // Drop documents of sessions whose files are gone
+    let removed: Vec<String> = manifest
+        .files
+        .keys()
+        .filter(|key| !seen_files.contains(*key))
+        .cloned()
+        .collect();
+    for key in removed {
+        if let Some(state) = manifest.files.remove(&key) {
+            delete_session_documents(&index_writer, &fields, &state.project_id, &state.session_id)?;
+            queue_embed_task(EmbedTask::Remove {
+                project_id: state.project_id,
+                session_id: state.session_id,
+            });
+        }
+    }
+
+    index_writer.commit().map_err(|e| e.to_string())?;
+    save_index_manifest(&manifest)?;
+
+    // Store search index in global state
+    let mut guard = SEARCH_INDEX.lock().map_err(|e| e.to_string())?;
+    *guard = Some(SearchIndex { index, schema });
+
+    // Write command stats to file, aggregated from every indexed session
+    let mut command_stats: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for state in manifest.files.values() {
+        for (raw_name, weeks) in &state.commands {
+            let name = alias_map.get(raw_name).cloned().unwrap_or_else(|| raw_name.clone());
+            let stats = command_stats.entry(name).or_default();
+            for (week_key, count) in weeks {
+                *stats.entry(week_key.clone()).or_insert(0) += count;
+            }
+        }
+    }
+
+    let stats_path = get_command_stats_path();
+    if let Some(parent) = stats_path.parent() {
+        fs::create_dir_all(parent).ok();
+    }
+    let stats_json = serde_json::json!({
+        "updated_at": chrono::Utc::now().timestamp(),
+        "commands": command_stats,
+    });
+    fs::write(&stats_path, serde_json::to_string_pretty(&stats_json).unwrap_or_default()).ok();
+
+    *manifest_guard = Some(manifest);
+    Ok(indexed_count)
+}
+
+/// Why the search index could not be used
+#[derive(Debug)]
+enum SearchIndexError {
+    NotBuilt,
+    /// Written with another schema version; a background rebuild has been started
+    SchemaMismatch { found: Option<u32>, expected: u32 },
+    Other(String),
+}
+
+impl std::fmt::Display for SearchIndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotBuilt => write!(f, "Search index not built. Please build index first."),
+            Self::SchemaMismatch { found, expected } => write!(
+                f,
+                "Search index schema version {} does not match {}. Rebuilding index in the background.",
+                found.map(|v| v.to_string()).unwrap_or_else(|| "unknown".to_string()),
+                expected
+            ),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for SearchIndexError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<SearchIndexError> for String {
+    fn from(error: SearchIndexError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Sent to the frontend as `{ kind, message }`
+impl Serialize for SearchIndexError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let kind = match self {
+            Self::NotBuilt => "not_built",
+            Self::SchemaMismatch { .. } => "schema_mismatch",
+            Self::Other(_) => "other",
+        };
+        let mut state = serializer.serialize_struct("SearchIndexError", 2)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Rebuild the search index on a background thread, unless a rebuild is already running
+fn start_search_index_rebuild() {
+    use std::sync::atomic::Ordering;
+
+    if SEARCH_INDEX_REBUILDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        if let Err(e) = sync_search_index() {
+            eprintln!("Failed to rebuild search index: {}", e);
+        }
+        SEARCH_INDEX_REBUILDING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Load the on-disk index into the global state if it is not loaded yet.
+/// An index with another schema version is never opened; it is rebuilt in the background.
+fn load_search_index(guard: &mut Option<SearchIndex>) -> Result<&SearchIndex, SearchIndexError> {
+    if guard.is_none() {
+        let index_dir = get_index_dir();
+        if !index_dir.exists() {
+            return Err(SearchIndexError::NotBuilt);
+        }
+
+        let found = read_schema_version();
+        if found != Some(SEARCH_SCHEMA_VERSION) {
+            start_search_index_rebuild();
+            return Err(SearchIndexError::SchemaMismatch {
+                found,
+                expected: SEARCH_SCHEMA_VERSION,
+            });
+        }
+
+        let schema = create_schema();
+        let index = Index::open_in_dir(&index_dir).map_err(|e| e.to_string())?;
+        if index.schema() != schema {
+            // The schema changed without a version bump
+            start_search_index_rebuild();
+            return Err(SearchIndexError::SchemaMismatch {
+                found,
+                expected: SEARCH_SCHEMA_VERSION,
+            });
+        }
+        // Register jieba tokenizer for Chinese support
+        register_jieba_tokenizer(&index);
+        *guard = Some(SearchIndex { index, schema });
+    }
+
+    Ok(guard.as_ref().unwrap())
+}
+
+/// Quiet period before changed session files are flushed into the index
+const SEARCH_WATCH_DEBOUNCE_MS: u64 = 1000;
+/// Upper bound on how long a steadily growing session can delay a commit
+const SEARCH_WATCH_MAX_DELAY_MS: u64 = 5000;
+
+/// Sync changed session files into the already built index.
+/// Does nothing until `build_search_index` has run once.
+fn sync_changed_session_files(paths: &std::collections::HashSet<PathBuf>) -> Result<usize, String> {
+    let mut manifest_guard = SEARCH_INDEX_MANIFEST.lock().map_err(|e| e.to_string())?;
+    if manifest_guard.is_none() {
+        *manifest_guard = load_index_manifest();
+    }
+    let manifest = match manifest_guard.as_mut() {
+        Some(manifest) => manifest,
+        None => return Ok(0),
+    };
+
+    let result = apply_session_file_changes(manifest, paths);
+    if result.is_err() {
+        // Uncommitted changes are rolled back, so reload the manifest from disk next time
+        *manifest_guard = None;
+    }
+    result
+}
+
+fn apply_session_file_changes(
+    manifest: &mut IndexManifest,
+    paths: &std::collections::HashSet<PathBuf>,
+) -> Result<usize, String> {
+    let (index, schema) = {
+        let mut guard = SEARCH_INDEX.lock().map_err(|e| e.to_string())?;
+        let search_index = load_search_index(&mut guard)?;
+        (search_index.index.clone(), search_index.schema.clone())
+    };
+
+    let mut index_writer: IndexWriter = index
+        .writer(15_000_000)
+        .map_err(|e| e.to_string())?;
+    let fields = SearchFields::new(&schema);
+    let command_pattern = regex::Regex::new(r"<command-name>(/[^<]+)</command-name>")
+        .map_err(|e| e.to_string())?;
+    let mut indexed_count = 0;
+    // Sessions are re-embedded by the next full build when the model changed
+    let embed = semantic_index::is_enabled() && !semantic_index::needs_rebuild();
+
+    for path in paths {
+        let key = path.to_string_lossy().to_string();
+
+        if !path.exists() {
+            if let Some(state) = manifest.files.remove(&key) {
+                delete_session_documents(&index_writer, &fields, &state.project_id, &state.session_id)?;
+                queue_embed_task(EmbedTask::Remove {
+                    project_id: state.project_id,
+                    session_id: state.session_id,
+                });
+            }
+            continue;
+        }
+
+        let project_id = match session_file_project_dir(path).and_then(|p| p.file_name()) {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        let display_path = decode_project_path(&project_id);
+        let previous = manifest.files.remove(&key);
+        let mut sync = sync_session_file(
+            &index_writer,
+            &fields,
+            &command_pattern,
+            path,
+            &project_id,
+            &display_path,
+            previous,
+        )?;
+        if embed {
+            embed_session_messages(&mut sync);
+        }
+        indexed_count += sync.added;
+        manifest.files.insert(key, sync.entry);
+    }
+
+    index_writer.commit().map_err(|e| e.to_string())?;
+    save_index_manifest(manifest)?;
+    Ok(indexed_count)
+}
+
+/// Watch ~/.claude/projects and stream appended session lines into the search index.
+/// Every batch of changes also wakes the budget check.
+fn watch_projects_for_search(app_handle: tauri::AppHandle) {
+    let projects_dir = get_claude_dir().join("projects");
+    if !projects_dir.exists() {
+        return;
+    }
+
+    let (tx, rx) = channel::<PathBuf>();
+    let watched_dir = projects_dir.clone();
+    // Events carry resolved paths on macOS and through a symlinked ~/.claude
+    let canonical_dir = projects_dir.canonicalize().unwrap_or_else(|_| projects_dir.clone());
+    let mut watcher: RecommendedWatcher = match notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+        if let Ok(event) = res {
+            if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+                for path in event.paths {
+                    // Map back under ~/.claude/projects, the paths the index is keyed by
+                    let path = match path.strip_prefix(&watched_dir).or_else(|_| path.strip_prefix(&canonical_dir)) {
+                        Ok(relative) => watched_dir.join(relative),
+                        Err(_) => continue,
+                    };
+                    // Only session and subagent files of a project directory
+                    if session_file_project_dir(&path).and_then(|p| p.parent()) != Some(watched_dir.as_path()) {
+                        continue;
+                    }
+                    if path.extension().is_some_and(|e| e == "jsonl") {
+                        let _ = tx.send(path);
+                    }
+                }
+            }
+        }
+    }) {
+        Ok(w) => w,
+        Err(_) => return,
+    };
+
+    if watcher.watch(&projects_dir, RecursiveMode::Recursive).is_err() {
+        return;
+    }
+
+    while let Ok(path) = rx.recv() {
+        // Debounce: collect changes until the files settle, but commit at least every few seconds
+        let mut changed = std::collections::HashSet::from([path]);
+        let deadline = std::time::Instant::now() + Duration::from_millis(SEARCH_WATCH_MAX_DELAY_MS);
+        while std::time::Instant::now() < deadline {
+            match rx.recv_timeout(Duration::from_millis(SEARCH_WATCH_DEBOUNCE_MS)) {
+                Ok(path) => {
+                    changed.insert(path);
+                }
+                Err(_) => break,
+            }
+        }
+
+        match sync_changed_session_files(&changed) {
+            Ok(count) if count > 0 => {
+                let _ = app_handle.emit("search-index-updated", count);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to update search index: {}", e),
+        }
+        // New usage may cross a budget threshold
+        budget_alerts::request_check();
+    }
+}
+
+/// Parse a session timestamp (RFC 3339) into an index date
+fn parse_search_timestamp(value: &str) -> Option<tantivy::DateTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| tantivy::DateTime::from_timestamp_millis(dt.timestamp_millis()))
+}
+
+/// Structured filters applied inside the search query
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchFilters {
+    pub project_id: Option<String>,
+    pub session_id: Option<String>,
+    pub role: Option<String>, // "user" | "assistant"
+    pub from: Option<String>, // RFC 3339, inclusive
+    pub to: Option<String>,   // RFC 3339, exclusive
+    pub has_tool: Option<bool>,
+    pub tool_name: Option<String>, // e.g. "Bash", "Edit"
+    pub is_subagent: Option<bool>,
+    pub tags: Option<Vec<String>>, // sessions carrying all of these tags
+}
+
+/// Build the non-scoring sub-queries for the given filters
+fn build_filter_queries(
+    schema: &Schema,
+    filters: &SearchFilters,
+) -> Result<Vec<Box<dyn tantivy::query::Query>>, String> {
+    use std::ops::Bound;
+    use tantivy::query::{ConstScoreQuery, Query, RangeQuery, TermQuery};
+
+    let filter = |query: Box<dyn Query>| -> Box<dyn Query> { Box::new(ConstScoreQuery::new(query, 0.0)) };
+    let term_filter = |field_name: &str, value: &str| -> Box<dyn Query> {
+        let field = schema.get_field(field_name).unwrap();
+        filter(Box::new(TermQuery::new(
+            Term::from_field_text(field, value),
+            IndexRecordOption::Basic,
+        )))
+    };
+
+    let mut queries = Vec::new();
+
+    if let Some(project_id) = &filters.project_id {
+        queries.push(term_filter("project_id", project_id));
+    }
+    if let Some(session_id) = &filters.session_id {
+        queries.push(term_filter("session_id", session_id));
+    }
+    if let Some(role) = &filters.role {
+        queries.push(term_filter("role", role));
+    }
+    if let Some(tool_name) = &filters.tool_name {
+        queries.push(term_filter("tool_name", tool_name));
+    }
+    if let Some(has_tool) = filters.has_tool {
+        let field = schema.get_field("is_tool").unwrap();
+        queries.push(filter(Box::new(TermQuery::new(
+            Term::from_field_bool(field, has_tool),
+            IndexRecordOption::Basic,
+        ))));
+    }
+    if let Some(is_subagent) = filters.is_subagent {
+        let field = schema.get_field("is_subagent").unwrap();
+        queries.push(filter(Box::new(TermQuery::new(
+            Term::from_field_bool(field, is_subagent),
+            IndexRecordOption::Basic,
+        ))));
+    }
+    for tag in filters.tags.iter().flatten() {
+        let field = schema.get_field("tags").unwrap();
+        let facet = Facet::from_path([tag.trim().to_lowercase()]);
+        queries.push(filter(Box::new(TermQuery::new(
+            Term::from_facet(field, &facet),
+            IndexRecordOption::Basic,
+        ))));
+    }
+
+    let parse_bound = |value: &Option<String>| -> Result<Option<tantivy::DateTime>, String> {
+        match value {
+            Some(v) => parse_search_timestamp(v)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid date: {}", v)),
+            None => Ok(None),
+        }
+    };
+    let from = parse_bound(&filters.from)?;
+    let to = parse_bound(&filters.to)?;
+    if from.is_some() || to.is_some() {
+        queries.push(filter(Box::new(RangeQuery::new_date_bounds(
+            "timestamp".to_string(),
+            from.map_or(Bound::Unbounded, Bound::Included),
+            to.map_or(Bound::Unbounded, Bound::Excluded),
+        ))));
+    }
+
+    Ok(queries)
+}
+
+/// Maximum snippet length around the best match
+const SNIPPET_MAX_CHARS: usize = 200;
+
+/// Convert a tantivy snippet to UTF-16 highlight ranges.
+/// Falls back to the start of the message when the match is only in the session summary.
+fn build_search_snippet(snippet: &Snippet, content: &str) -> SearchSnippet {
+    if snippet.is_empty() {
+        return SearchSnippet {
+            fragment: content.chars().take(SNIPPET_MAX_CHARS).collect(),
+            highlights: Vec::new(),
+        };
+    }
+
+    let fragment = snippet.fragment();
+    let utf16_offset = |byte_offset: usize| fragment[..byte_offset].encode_utf16().count();
+    let highlights = snippet
+        .highlighted()
+        .iter()
+        .map(|range| HighlightRange {
+            start: utf16_offset(range.start),
+            end: utf16_offset(range.end),
+        })
+        .collect();
+
+    SearchSnippet {
+        fragment: fragment.to_string(),
+        highlights,
+    }
+}
+
+/// How the search text is turned into a query
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryMode {
+    /// Full query syntax, invalid queries return no results
+    #[default]
+    Strict,
+    /// Full query syntax, invalid parts are skipped
+    Lenient,
+    /// Every word matches with typo tolerance
+    Fuzzy,
+    /// Search-as-you-type, the last word matches as a prefix
+    Prefix,
+}
+
+/// Split search text into index terms with the same analyzer used for `content`
+fn tokenize_search_text(index: &Index, field: Field, text: &str) -> Vec<String> {
+    let mut analyzer = match index.tokenizer_for_field(field) {
+        Ok(analyzer) => analyzer,
+        Err(_) => return Vec::new(),
+    };
+    let mut terms = Vec::new();
+    analyzer.token_stream(text).process(&mut |token| {
+        // Skip separators such as `/` or `-` that jieba emits as tokens
+        if token.text.chars().any(|c| c.is_alphanumeric()) {
+            terms.push(token.text.clone());
+        }
+    });
+    terms
+}
+
+/// Build the text part of a search query, returning parse warnings instead of failing
+fn build_text_query(
+    index: &Index,
+    fields: &[Field],
+    text: &str,
+    mode: QueryMode,
+) -> (Box<dyn tantivy::query::Query>, Vec<String>) {
+    use tantivy::query::{BooleanQuery, EmptyQuery, FuzzyTermQuery, Occur, Query, TermQuery};
+
+    let query_parser = QueryParser::for_index(index, fields.to_vec());
+
+    match mode {
+        QueryMode::Strict => match query_parser.parse_query(text) {
+            Ok(query) => (query, Vec::new()),
+            Err(e) => (Box::new(EmptyQuery), vec![e.to_string()]),
+        },
+        QueryMode::Lenient => {
+            let (query, errors) = query_parser.parse_query_lenient(text);
+            (query, errors.iter().map(|e| e.to_string()).collect())
+        }
+        QueryMode::Fuzzy | QueryMode::Prefix => {
+            let terms = tokenize_search_text(index, fields[0], text);
+            let last = terms.len().saturating_sub(1);
+
+            let clauses: Vec<(Occur, Box<dyn Query>)> = terms
+                .iter()
+                .enumerate()
+                .map(|(i, term_text)| {
+                    // A term may match in any of the fields
+                    let per_field: Vec<(Occur, Box<dyn Query>)> = fields
+                        .iter()
+                        .map(|&field| {
+                            let term = Term::from_field_text(field, term_text);
+                            let query: Box<dyn Query> = match mode {
+                                QueryMode::Fuzzy => {
+                                    // Allow more typos in longer words
+                                    let distance = match term_text.chars().count() {
+                                        0..=2 => 0,
+                                        3..=5 => 1,
+                                        _ => 2,
+                                    };
+                                    Box::new(FuzzyTermQuery::new(term, distance, true))
+                                }
+                                _ if i == last => Box::new(FuzzyTermQuery::new_prefix(term, 0, true)),
+                                _ => Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)),
+                            };
+                            (Occur::Should, query)
+                        })
+                        .collect();
+                    (Occur::Must, Box::new(BooleanQuery::new(per_field)) as Box<dyn Query>)
+                })
+                .collect();
+
+            if clauses.is_empty() {
+                (Box::new(EmptyQuery), Vec::new())
+            } else {
+                (Box::new(BooleanQuery::new(clauses)), Vec::new())
+            }
+        }
+    }
+}
+
+/// A parsed search ready to run against the current index snapshot
+struct PreparedSearch {
+    searcher: tantivy::Searcher,
+    schema: Schema,
+    query: Box<dyn tantivy::query::Query>,
+    snippet_generator: SnippetGenerator,
+    tool_snippet_generator: SnippetGenerator, // for messages that only contain tool calls
+    warnings: Vec<String>,
+}
+
+fn prepare_search(
+    query: &str,
+    mode: QueryMode,
+    filters: &SearchFilters,
+) -> Result<PreparedSearch, SearchIndexError> {
+    use tantivy::query::{BooleanQuery, Occur};
+
+    // Try to get index from global state or load from disk
+    let mut guard = SEARCH_INDEX.lock().map_err(|e| e.to_string())?;
+    let search_index = load_search_index(&mut guard)?;
+    let reader = search_index
+        .index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::OnCommitWithDelay)
+        .try_into()
+        .map_err(|e: tantivy::TantivyError| e.to_string())?;
+
+    let searcher = reader.searcher();
+
+    let content_field = search_index.schema.get_field("content").unwrap();
+    let session_summary_field = search_index.schema.get_field("session_summary").unwrap();
+    let tool_input_field = search_index.schema.get_field("tool_input").unwrap();
+
+    let (parsed_query, warnings) = build_text_query(
+        &search_index.index,
+        &[content_field, session_summary_field],
+        query,
+        mode,
+    );
+
+    let mut snippet_generator = SnippetGenerator::create(&searcher, &*parsed_query, content_field)
+        .map_err(|e| e.to_string())?;
+    snippet_generator.set_max_num_chars(SNIPPET_MAX_CHARS);
+    let mut tool_snippet_generator = SnippetGenerator::create(&searcher, &*parsed_query, tool_input_field)
+        .map_err(|e| e.to_string())?;
+    tool_snippet_generator.set_max_num_chars(SNIPPET_MAX_CHARS);
+
+    // Filters are part of the query so that `limit` applies to matching documents only
+    let mut clauses = vec![(Occur::Must, parsed_query)];
+    for filter_query in build_filter_queries(&search_index.schema, filters)? {
+        clauses.push((Occur::Must, filter_query));
+    }
+
+    Ok(PreparedSearch {
+        searcher,
+        schema: search_index.schema.clone(),
+        query: Box::new(BooleanQuery::new(clauses)),
+        snippet_generator,
+        tool_snippet_generator,
+        warnings,
+    })
+}
+
+/// Load a matched document as a search result
+fn read_search_result(
+    search: &PreparedSearch,
+    score: f32,
+    doc_address: tantivy::DocAddress,
+) -> Result<SearchResult, String> {
+    let retrieved_doc: tantivy::TantivyDocument =
+        search.searcher.doc(doc_address).map_err(|e| e.to_string())?;
+
+    let get_text = |field_name: &str| -> String {
+        let field = search.schema.get_field(field_name).unwrap();
+        retrieved_doc
+            .get_first(field)
+            .and_then(|v| TantivyValue::as_str(&v))
+            .unwrap_or("")
+            .to_string()
+    };
+
+    let timestamp_field = search.schema.get_field("timestamp").unwrap();
+    let timestamp = retrieved_doc
+        .get_first(timestamp_field)
+        .and_then(|v| TantivyValue::as_datetime(&v))
+        .and_then(|dt| chrono::DateTime::from_timestamp_millis(dt.into_timestamp_millis()))
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default();
+
+    let line_number_field = search.schema.get_field("line_number").unwrap();
+    let line_number = retrieved_doc
+        .get_first(line_number_field)
+        .and_then(|v| TantivyValue::as_u64(&v))
+        .unwrap_or(0) as usize;
+
+    let content = get_text("content");
+    let snippet = if content.is_empty() {
+        let tool_input = get_text("tool_input");
+        build_search_snippet(&search.tool_snippet_generator.snippet(&tool_input), &tool_input)
+    } else {
+        build_search_snippet(&search.snippet_generator.snippet(&content), &content)
+    };
+    let summary = get_text("session_summary");
+    let parent_session_id = get_text("parent_session_id");
+    let is_subagent_field = search.schema.get_field("is_subagent").unwrap();
+    let is_subagent = retrieved_doc
+        .get_first(is_subagent_field)
+        .and_then(|v| TantivyValue::as_bool(&v))
+        .unwrap_or(false);
+    let tags_field = search.schema.get_field("tags").unwrap();
+    let tags = retrieved_doc
+        .get_all(tags_field)
+        .filter_map(|v| TantivyValue::as_facet(&v).map(|facet| facet.to_path().join("/")))
+        .collect();
+
+    Ok(SearchResult {
+        uuid: get_text("uuid"),
+        content,
+        snippet,
+        line_number,
+        role: get_text("role"),
+        project_id: get_text("project_id"),
+        project_path: get_text("project_path"),
+        session_id: get_text("session_id"),
+        session_summary: if summary.is_empty() {
+            None
+        } else {
+            Some(summary)
+        },
+        timestamp,
+        score,
+        is_subagent,
+        parent_session_id: if parent_session_id.is_empty() {
+            None
+        } else {
+            Some(parent_session_id)
+        },
+        source: get_text("source"),
+        tags,
     })
-    .await
-    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub warnings: Vec<String>, // query parse problems, results may be partial
 }
 
 #[tauri::command]
 fn search_chats(
     query: String,
     limit: Option<usize>,
-    project_id: Option<String>,
-) -> Result<Vec<SearchResult>, String> {
+    mode: Option<QueryMode>,
+    filters: Option<SearchFilters>,
+) -> Result<SearchResponse, SearchIndexError> {
     let max_results = limit.unwrap_or(50);
+    let search = prepare_search(&query, mode.unwrap_or_default(), &filters.unwrap_or_default())?;
 
-    // Try to get index from global state or load from disk
-    let mut guard = SEARCH_INDEX.lock().map_err(|e| e.to_string())?;
+    let top_docs = search
+        .searcher
+        .search(&*search.query, &TopDocs::with_limit(max_results))
+        .map_err(|e| e.to_string())?;
 
-    if guard.is_none() {
-        let index_dir = get_index_dir();
-        if !index_dir.exists() {
-            return Err("Search index not built. Please build index first.".to_string());
+    let results = top_docs
+        .into_iter()
+        .map(|(score, doc_address)| read_search_result(&search, score, doc_address))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(SearchResponse {
+        results,
+        warnings: search.warnings,
+    })
+}
+
+/// Matches of one session, aggregated while collecting
+#[derive(Debug, Clone, Copy)]
+struct SessionMatchGroup {
+    score: f32,
+    hit_count: usize,
+    best_score: f32,
+    best_doc: tantivy::DocAddress,
+}
+
+impl SessionMatchGroup {
+    fn merge(&mut self, other: &SessionMatchGroup) {
+        self.score += other.score;
+        self.hit_count += other.hit_count;
+        if other.best_score > self.best_score {
+            self.best_score = other.best_score;
+            self.best_doc = other.best_doc;
         }
+    }
+}
 
-        let schema = create_schema();
-        let index = Index::open_in_dir(&index_dir).map_err(|e| e.to_string())?;
-        // Register jieba tokenizer for Chinese support
-        register_jieba_tokenizer(&index);
-        *guard = Some(SearchIndex { index, schema });
+/// Collector grouping every match by its `session_id` fast field
+struct SessionGroupCollector;
+
+struct SessionGroupSegmentCollector {
+    segment_ord: u32,
+    session_ids: Option<tantivy::columnar::StrColumn>,
+    groups: HashMap<u64, SessionMatchGroup>, // session_id term ordinal -> group
+}
+
+impl tantivy::collector::Collector for SessionGroupCollector {
+    type Fruit = HashMap<String, SessionMatchGroup>;
+    type Child = SessionGroupSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_ord: u32,
+        segment: &tantivy::SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(SessionGroupSegmentCollector {
+            segment_ord,
+            session_ids: segment.fast_fields().str("session_id")?,
+            groups: HashMap::new(),
+        })
     }
 
-    let search_index = guard.as_ref().unwrap();
-    let reader = search_index
-        .index
-        .reader_builder()
-        .reload_policy(ReloadPolicy::OnCommitWithDelay)
-        .try_into()
-        .map_err(|e: tantivy::TantivyError| e.to_string())?;
+    fn requires_scoring(&self) -> bool {
+        true
+    }
 
-    let searcher = reader.searcher();
+    fn merge_fruits(&self, fruits: Vec<Self::Fruit>) -> tantivy::Result<Self::Fruit> {
+        let mut merged: Self::Fruit = HashMap::new();
+        for fruit in fruits {
+            for (session_id, group) in fruit {
+                merged
+                    .entry(session_id)
+                    .and_modify(|existing| existing.merge(&group))
+                    .or_insert(group);
+            }
+        }
+        Ok(merged)
+    }
+}
 
-    let content_field = search_index.schema.get_field("content").unwrap();
-    let session_summary_field = search_index.schema.get_field("session_summary").unwrap();
+impl tantivy::collector::SegmentCollector for SessionGroupSegmentCollector {
+    type Fruit = HashMap<String, SessionMatchGroup>;
 
-    let query_parser = QueryParser::for_index(
-        &search_index.index,
-        vec![content_field, session_summary_field],
-    );
-    let parsed_query = query_parser
-        .parse_query(&query)
-        .map_err(|e| e.to_string())?;
+    fn collect(&mut self, doc: tantivy::DocId, score: f32) {
+        let Some(ord) = self.session_ids.as_ref().and_then(|c| c.term_ords(doc).next()) else {
+            return;
+        };
+        let group = SessionMatchGroup {
+            score,
+            hit_count: 1,
+            best_score: score,
+            best_doc: tantivy::DocAddress::new(self.segment_ord, doc),
+        };
+        self.groups
+            .entry(ord)
+            .and_modify(|existing| existing.merge(&group))
+            .or_insert(group);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let Some(session_ids) = self.session_ids else {
+            return HashMap::new();
+        };
+        self.groups
+            .into_iter()
+            .filter_map(|(ord, group)| {
+                let mut session_id = String::new();
+                match session_ids.ord_to_str(ord, &mut session_id) {
+                    Ok(true) => Some((session_id, group)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// One session in grouped search results
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionSearchHit {
+    pub session_id: String,
+    pub project_id: String,
+    pub project_path: String,
+    pub session_summary: Option<String>,
+    pub score: f32,     // sum of the scores of all matching messages
+    pub hit_count: usize,
+    pub best_match: SearchResult,
+}
 
-    let top_docs = searcher
-        .search(&parsed_query, &TopDocs::with_limit(max_results))
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionSearchResponse {
+    pub items: Vec<SessionSearchHit>,
+    pub total: usize, // number of matching sessions
+    pub warnings: Vec<String>,
+}
+
+/// Search grouped by session: one hit per session, paged across sessions
+#[tauri::command]
+fn search_chat_sessions(
+    query: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    mode: Option<QueryMode>,
+    filters: Option<SearchFilters>,
+) -> Result<SessionSearchResponse, SearchIndexError> {
+    let max_sessions = limit.unwrap_or(20);
+    let skip = offset.unwrap_or(0);
+    let search = prepare_search(&query, mode.unwrap_or_default(), &filters.unwrap_or_default())?;
+
+    let groups = search
+        .searcher
+        .search(&*search.query, &SessionGroupCollector)
         .map_err(|e| e.to_string())?;
 
-    let mut results = Vec::new();
+    let mut groups: Vec<(String, SessionMatchGroup)> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then_with(|| a.0.cmp(&b.0)));
+    let total = groups.len();
 
-    for (score, doc_address) in top_docs {
-        let retrieved_doc: tantivy::TantivyDocument =
-            searcher.doc(doc_address).map_err(|e| e.to_string())?;
+    let items = groups
+        .into_iter()
+        .skip(skip)
+        .take(max_sessions)
+        .map(|(session_id, group)| {
+            let best_match = read_search_result(&search, group.best_score, group.best_doc)?;
+            Ok(SessionSearchHit {
+                session_id,
+                project_id: best_match.project_id.clone(),
+                project_path: best_match.project_path.clone(),
+                session_summary: best_match.session_summary.clone(),
+                score: group.score,
+                hit_count: group.hit_count,
+                best_match,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
 
-        let get_text = |field_name: &str| -> String {
-            let field = search_index.schema.get_field(field_name).unwrap();
-            retrieved_doc
-                .get_first(field)
-                .and_then(|v| TantivyValue::as_str(&v))
-                .unwrap_or("")
-                .to_string()
-        };
+    Ok(SessionSearchResponse {
+        items,
+        total,
+        warnings: search.warnings,
+    })
+}
+
+/// Candidates taken from each side (keyword and vector) per requested result
+const SEMANTIC_CANDIDATE_FACTOR: usize = 4;
+/// Share of the normalized BM25 score in the hybrid score, the rest is cosine similarity
+const SEMANTIC_KEYWORD_WEIGHT: f32 = 0.5;
+
+/// Hybrid search: BM25 keyword candidates and nearest embedded messages, re-ranked by
+/// a blend of normalized BM25 score and cosine similarity to the query
+#[tauri::command]
+async fn semantic_search_chats(
+    query: String,
+    limit: Option<usize>,
+    filters: Option<SearchFilters>,
+) -> Result<SearchResponse, SearchIndexError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        use tantivy::query::{BooleanQuery, Occur, TermQuery};
+
+        let max_results = limit.unwrap_or(20);
+        let candidates = max_results * SEMANTIC_CANDIDATE_FACTOR;
+        let filters = filters.unwrap_or_default();
+
+        let query_vector = semantic_index::embed_query(&query)?;
+        let similarities = semantic_index::similarities(
+            &query_vector,
+            filters.project_id.as_deref(),
+            filters.session_id.as_deref(),
+        )?;
+
+        let search = prepare_search(&query, QueryMode::Lenient, &filters)?;
+        let keyword_hits = search
+            .searcher
+            .search(&*search.query, &TopDocs::with_limit(candidates))
+            .map_err(|e| e.to_string())?;
+        let max_bm25 = keyword_hits.iter().map(|(score, _)| *score).fold(0.0, f32::max);
+
+        let mut results: HashMap<String, SearchResult> = HashMap::new();
+        for (bm25, doc_address) in keyword_hits {
+            let mut result = read_search_result(&search, 0.0, doc_address)?;
+            let similarity = similarities.get(&result.uuid).copied().unwrap_or(0.0);
+            let keyword = if max_bm25 > 0.0 { bm25 / max_bm25 } else { 0.0 };
+            result.score = SEMANTIC_KEYWORD_WEIGHT * keyword + (1.0 - SEMANTIC_KEYWORD_WEIGHT) * similarity;
+            results.insert(result.uuid.clone(), result);
+        }
 
-        let doc_project_id = get_text("project_id");
+        // Nearest messages the keyword query missed; filters still apply
+        let mut nearest: Vec<(&String, &f32)> = similarities.iter().collect();
+        nearest.sort_by(|a, b| b.1.total_cmp(a.1));
+        let uuid_field = search.schema.get_field("uuid").unwrap();
+        let filter_queries = build_filter_queries(&search.schema, &filters)?;
 
-        // Filter by project_id if specified
-        if let Some(ref filter_id) = project_id {
-            if &doc_project_id != filter_id {
+        for (uuid, similarity) in nearest.into_iter().take(candidates) {
+            if results.contains_key(uuid) {
                 continue;
             }
+            let mut clauses: Vec<(Occur, Box<dyn tantivy::query::Query>)> = vec![(
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(uuid_field, uuid),
+                    IndexRecordOption::Basic,
+                )),
+            )];
+            for filter_query in &filter_queries {
+                clauses.push((Occur::Must, filter_query.box_clone()));
+            }
+            let hit = search
+                .searcher
+                .search(&BooleanQuery::new(clauses), &TopDocs::with_limit(1))
+                .map_err(|e| e.to_string())?;
+            if let Some((_, doc_address)) = hit.into_iter().next() {
+                let mut result = read_search_result(&search, 0.0, doc_address)?;
+                result.score = (1.0 - SEMANTIC_KEYWORD_WEIGHT) * similarity;
+                results.insert(uuid.clone(), result);
+            }
         }
 
-        let summary = get_text("session_summary");
-
-        results.push(SearchResult {
-            uuid: get_text("uuid"),
-            content: get_text("content"),
-            role: get_text("role"),
-            project_id: doc_project_id,
-            project_path: get_text("project_path"),
-            session_id: get_text("session_id"),
-            session_summary: if summary.is_empty() {
-                None
-            } else {
-                Some(summary)
-            },
-            timestamp: get_text("timestamp"),
-            score,
-        });
-    }
+        let mut results: Vec<SearchResult> = results.into_values().collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.uuid.cmp(&b.uuid)));
+        results.truncate(max_results);
 
-    Ok(results)
+        Ok(SearchResponse {
+            results,
+            warnings: search.warnings,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 fn extract_content_with_meta(value: &Option<serde_json::Value>) -> (String, bool) {
@@ -1379,6 +3728,171 @@ fn extract_content_with_meta(value: &Option<serde_json::Value>) -> (String, bool
     }
 }
 
+/// Parse message content into typed blocks; unknown block types are skipped
+fn parse_content_blocks(value: &Value) -> Vec<ContentBlock> {
+    let items = match value {
+        Value::String(text) => {
+            return vec![ContentBlock::Text { text: text.clone() }];
+        }
+        Value::Array(items) => items,
+        _ => return Vec::new(),
+    };
+
+    let get_str = |item: &Value, key: &str| -> String {
+        item.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+    };
+
+    items
+        .iter()
+        .filter_map(|item| match item.get("type").and_then(|v| v.as_str())? {
+            "text" => Some(ContentBlock::Text {
+                text: get_str(item, "text"),
+            }),
+            "thinking" => Some(ContentBlock::Thinking {
+                thinking: get_str(item, "thinking"),
+            }),
+            "tool_use" => Some(ContentBlock::ToolUse {
+                id: get_str(item, "id"),
+                name: get_str(item, "name"),
+                input: item.get("input").cloned().unwrap_or(Value::Null),
+                result_uuid: None,
+            }),
+            "tool_result" => Some(ContentBlock::ToolResult {
+                tool_use_id: get_str(item, "tool_use_id"),
+                tool_name: None,
+                is_error: item.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false),
+                content: item.get("content").map(parse_content_blocks).unwrap_or_default(),
+            }),
+            "image" => {
+                let source = item.get("source")?;
+                let media_type = source.get("media_type").and_then(|v| v.as_str()).map(String::from);
+                let source = match source.get("type").and_then(|v| v.as_str()) {
+                    Some("base64") => format!(
+                        "data:{};base64,{}",
+                        media_type.as_deref().unwrap_or("image/png"),
+                        get_str(source, "data")
+                    ),
+                    _ => get_str(source, "url"),
+                };
+                Some(ContentBlock::Image { media_type, source })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Link every tool_result to its tool_use by id, in both directions
+fn link_tool_blocks(messages: &mut [TranscriptMessage]) {
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+    let mut result_uuids: HashMap<String, String> = HashMap::new();
+    for message in messages.iter() {
+        for block in &message.blocks {
+            match block {
+                ContentBlock::ToolUse { id, name, .. } => {
+                    tool_names.insert(id.clone(), name.clone());
+                }
+                ContentBlock::ToolResult { tool_use_id, .. } => {
+                    result_uuids.insert(tool_use_id.clone(), message.uuid.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for block in messages.iter_mut().flat_map(|m| m.blocks.iter_mut()) {
+        match block {
+            ContentBlock::ToolUse { id, result_uuid, .. } => {
+                *result_uuid = result_uuids.get(id).cloned();
+            }
+            ContentBlock::ToolResult {
+                tool_use_id,
+                tool_name,
+                ..
+            } => {
+                *tool_name = tool_names.get(tool_use_id).cloned();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Maximum length of each tool call's input and output kept in the index
+const TOOL_TEXT_MAX_CHARS: usize = 10_000;
+
+/// Tool calls and results found in a message's content blocks
+#[derive(Debug, Default)]
+struct ToolActivity {
+    names: Vec<String>,
+    inputs: Vec<String>,
+    file_paths: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl ToolActivity {
+    fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.inputs.is_empty() && self.outputs.is_empty()
+    }
+}
+
+/// Collect every string value nested in a tool input
+fn collect_json_strings(value: &serde_json::Value, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => out.push(s.clone()),
+        serde_json::Value::Array(arr) => arr.iter().for_each(|v| collect_json_strings(v, out)),
+        serde_json::Value::Object(obj) => obj.values().for_each(|v| collect_json_strings(v, out)),
+        _ => {}
+    }
+}
+
+fn extract_tool_activity(value: &Option<serde_json::Value>) -> ToolActivity {
+    let mut activity = ToolActivity::default();
+    let Some(serde_json::Value::Array(arr)) = value else {
+        return activity;
+    };
+
+    for item in arr {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("tool_use") => {
+                if let Some(name) = item.get("name").and_then(|v| v.as_str()) {
+                    activity.names.push(name.to_string());
+                }
+                if let Some(input) = item.get("input") {
+                    // Edit/Write/MultiEdit/Read use file_path, NotebookEdit uses notebook_path
+                    for key in ["file_path", "notebook_path"] {
+                        if let Some(path) = input.get(key).and_then(|v| v.as_str()) {
+                            activity.file_paths.push(path.to_string());
+                        }
+                    }
+                    // Write/Edit inputs carry whole files, cap them like outputs
+                    let mut strings = Vec::new();
+                    collect_json_strings(input, &mut strings);
+                    let text = strings.join("\n");
+                    if !text.is_empty() {
+                        activity.inputs.push(text.chars().take(TOOL_TEXT_MAX_CHARS).collect());
+                    }
+                }
+            }
+            Some("tool_result") => {
+                let output = match item.get("content") {
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(serde_json::Value::Array(blocks)) => blocks
+                        .iter()
+                        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    _ => String::new(),
+                };
+                if !output.is_empty() {
+                    activity.outputs.push(output.chars().take(TOOL_TEXT_MAX_CHARS).collect());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    activity
+}
+
 // ============================================================================
 // Commands Feature
 // ============================================================================
@@ -2674,6 +5188,7 @@ fn find_session_project(session_id: String) -> Result<Option<Session>, String> {
                 }
             }
 
+            let meta = session_meta::get(&project_id, &session_id);
             return Ok(Some(Session {
                 id: session_id,
                 project_id,
@@ -2682,6 +5197,8 @@ fn find_session_project(session_id: String) -> Result<Option<Session>, String> {
                 message_count: 0,
                 last_modified: 0,
                 usage: None,
+                source: CLAUDE_SESSION_SOURCE.to_string(),
+                meta,
             }));
         }
     }
@@ -4744,10 +7261,20 @@ fn get_settings() -> Result<ClaudeSettings, String> {
 }
 
 fn get_session_path(project_id: &str, session_id: &str) -> PathBuf {
-    get_claude_dir()
-        .join("projects")
-        .join(project_id)
-        .join(format!("{}.jsonl", session_id))
+    let project_dir = get_claude_dir().join("projects").join(project_id);
+    let path = project_dir.join(format!("{}.jsonl", session_id));
+
+    // Subagents may live under <session_id>/subagents/ instead
+    if !path.exists() && session_id.starts_with("agent-") {
+        let file_name = format!("{}.jsonl", session_id);
+        if let Some(nested) = list_nested_subagent_files(&project_dir)
+            .into_iter()
+            .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy() == file_name))
+        {
+            return nested;
+        }
+    }
+    path
 }
 
 #[tauri::command]
@@ -4818,6 +7345,169 @@ fn reveal_session_file(project_id: String, session_id: String) -> Result<(), Str
     Ok(())
 }
 
+// ============================================================================
+// Session Archive & Trash
+// ============================================================================
+
+/// Transcript of a session plus everything it spawned: its <session_id>/ directory
+/// (nested subagents, tool results) and subagents written next to it
+fn collect_session_files(project_id: &str, session_id: &str) -> Vec<PathBuf> {
+    let project_dir = get_claude_dir().join("projects").join(project_id);
+    let mut files = vec![get_session_path(project_id, session_id)];
+    let session_dir = project_dir.join(session_id);
+    if session_dir.is_dir() {
+        files.push(session_dir);
+    }
+    files.extend(
+        fs::read_dir(&project_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_subagent_file(path))
+            .filter(|path| read_subagent_parent(path).as_deref() == Some(session_id)),
+    );
+    files
+}
+
+/// Session files within `files`, as the search index keys them
+fn indexed_session_files(files: &[PathBuf]) -> std::collections::HashSet<PathBuf> {
+    let mut paths = std::collections::HashSet::new();
+    for path in files {
+        if path.is_dir() {
+            paths.extend(
+                fs::read_dir(path.join("subagents"))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| is_subagent_file(path)),
+            );
+        } else {
+            paths.insert(path.clone());
+        }
+    }
+    paths
+}
+
+fn remove_session_files(
+    project_id: &str,
+    session_id: &str,
+    kind: session_trash::RemovalKind,
+) -> Result<session_trash::TrashEntry, String> {
+    let path = get_session_path(project_id, session_id);
+    if !path.exists() {
+        return Err("Session file not found".to_string());
+    }
+    let (summary, _) = read_session_head(&path, 20);
+    let files = collect_session_files(project_id, session_id);
+    let indexed = indexed_session_files(&files);
+
+    let entry = session_trash::remove_session(project_id, session_id, summary, &files, kind)?;
+    // Paths that no longer exist are dropped from the index
+    if let Err(e) = sync_changed_session_files(&indexed) {
+        eprintln!("Failed to remove session {} from the search index: {}", session_id, e);
+    }
+    Ok(entry)
+}
+
+/// Move a session out of ~/.claude/projects and keep it until restored
+#[tauri::command]
+async fn archive_session(project_id: String, session_id: String) -> Result<session_trash::TrashEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        remove_session_files(&project_id, &session_id, session_trash::RemovalKind::Archived)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Move a session to the trash, purged after the retention period
+#[tauri::command]
+async fn trash_session(project_id: String, session_id: String) -> Result<session_trash::TrashEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry = remove_session_files(&project_id, &session_id, session_trash::RemovalKind::Trashed)?;
+        // The session is trashed either way, a failed purge is retried by the hourly one
+        if let Err(e) = session_trash::purge_expired() {
+            eprintln!("Failed to purge session trash: {}", e);
+        }
+        Ok(entry)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Put an archived or trashed session back, e.g. to undo `trash_session`
+#[tauri::command]
+async fn restore_session(entry_id: String) -> Result<session_trash::TrashEntry, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry = session_trash::restore(&entry_id)?;
+        let files: Vec<PathBuf> = entry.files.iter().map(|f| PathBuf::from(&f.original_path)).collect();
+        if let Err(e) = sync_changed_session_files(&indexed_session_files(&files)) {
+            eprintln!("Failed to index restored session {}: {}", entry.session_id, e);
+        }
+        Ok(entry)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// ============================================================================
+// Session Metadata
+// ============================================================================
+
+#[tauri::command]
+fn get_session_meta(project_id: String, session_id: String) -> session_meta::SessionMeta {
+    session_meta::get(&project_id, &session_id)
+}
+
+/// Update tags, star, note or title of a session. Changed tags are written into the
+/// search index right away.
+#[tauri::command]
+async fn update_session_meta(
+    project_id: String,
+    session_id: String,
+    update: session_meta::SessionMetaUpdate,
+) -> Result<session_meta::SessionMeta, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let previous_tags = session_meta::get(&project_id, &session_id).tags;
+        let meta = session_meta::update(&project_id, &session_id, update)?;
+        if meta.tags != previous_tags && get_session_path(&project_id, &session_id).exists() {
+            let files = indexed_session_files(&collect_session_files(&project_id, &session_id));
+            if let Err(e) = sync_changed_session_files(&files) {
+                eprintln!("Failed to index tags of session {}: {}", session_id, e);
+            }
+        }
+        Ok(meta)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Tags in use with their session counts, optionally within one project
+#[tauri::command]
+fn list_session_tags(project_id: Option<String>) -> Vec<session_meta::TagCount> {
+    session_meta::tag_counts(project_id.as_deref())
+}
+
+#[tauri::command]
+fn list_trashed_sessions() -> Vec<session_trash::TrashEntry> {
+    session_trash::list()
+}
+
+#[tauri::command]
+fn get_trash_settings() -> session_trash::TrashSettings {
+    session_trash::load_settings()
+}
+
+#[tauri::command]
+fn save_trash_settings(settings: session_trash::TrashSettings) -> Result<(), String> {
+    session_trash::save_settings(&settings)?;
+    if let Err(e) = session_trash::purge_expired() {
+        eprintln!("Failed to purge session trash: {}", e);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 fn reveal_path(path: String) -> Result<(), String> {
     let expanded = if path.starts_with("~") {
@@ -6434,6 +9124,87 @@ fn pty_flush_scrollback() {
     pty_manager::flush_all_scrollback()
 }
 
+/// Panel opened by `open_session_in_panel`
+#[derive(Debug, Serialize)]
+pub struct SessionPanel {
+    pub workspace_project_id: String,
+    pub feature_id: String,
+    pub panel: workspace_store::PanelState,
+}
+
+/// Continue a historical session in a new terminal panel, running `claude --resume` in
+/// the session's project directory. With `fork`, Claude Code branches it into a new
+/// session instead of appending to the original. The panel is added to the feature
+/// linked to the session, or to `feature_id`, or to a new feature.
+#[tauri::command]
+async fn open_session_in_panel(
+    project_id: String,
+    session_id: String,
+    fork: Option<bool>,
+    feature_id: Option<String>,
+) -> Result<SessionPanel, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        // Session IDs are UUIDs or transcript file stems, never anything the shell would interpret
+        let valid_id = !session_id.is_empty()
+            && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(format!("Invalid session ID: {}", session_id));
+        }
+        if !get_session_path(&project_id, &session_id).exists() {
+            return Err("Session file not found".to_string());
+        }
+        let cwd = decode_project_path(&project_id);
+        if !Path::new(&cwd).is_dir() {
+            return Err(format!("Project directory not found: {}", cwd));
+        }
+
+        let meta = session_meta::get(&project_id, &session_id);
+        let title = meta
+            .title
+            .or_else(|| get_session_summary(project_id.clone(), session_id.clone()).ok().flatten())
+            .unwrap_or_else(|| "Untitled".to_string());
+        let (workspace_project_id, feature_id) = workspace_store::find_or_create_session_feature(
+            &cwd,
+            &session_id,
+            feature_id.as_deref(),
+            title.clone(),
+        )?;
+
+        let mut command = format!("claude --resume {}", shell_escape::escape(session_id.as_str().into()));
+        if fork.unwrap_or(false) {
+            command.push_str(" --fork-session");
+        }
+        let pty_id = uuid::Uuid::new_v4().to_string();
+        pty_manager::create_session(pty_id.clone(), cwd.clone(), None, Some(command.clone()))?;
+
+        let tab_id = uuid::Uuid::new_v4().to_string();
+        let panel = workspace_store::PanelState {
+            id: uuid::Uuid::new_v4().to_string(),
+            sessions: vec![workspace_store::SessionState {
+                id: tab_id.clone(),
+                pty_id: pty_id.clone(),
+                title,
+                command: Some(command),
+            }],
+            active_session_id: tab_id,
+            is_shared: false,
+            cwd,
+        };
+        if let Err(e) = workspace_store::add_panel_to_feature(&workspace_project_id, &feature_id, panel.clone()) {
+            let _ = pty_manager::kill_session(&pty_id);
+            return Err(e);
+        }
+
+        Ok(SessionPanel {
+            workspace_project_id,
+            feature_id,
+            panel,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // ============================================================================
 // Workspace Commands
 // ============================================================================
@@ -7306,6 +10077,22 @@ pub fn run() {
             // Initialize PTY manager with app handle for event emission
             pty_manager::init(app.handle().clone());
 
+            // Keep the search index in sync with running sessions
+            let search_app_handle = app.handle().clone();
+            std::thread::spawn(move || watch_projects_for_search(search_app_handle));
+
+            // Purge trashed sessions past their retention, now and then for as long as the app runs
+            std::thread::spawn(|| loop {
+                if let Err(e) = session_trash::purge_expired() {
+                    eprintln!("Failed to purge session trash: {}", e);
+                }
+                std::thread::sleep(session_trash::PURGE_INTERVAL);
+            });
+
+            // Alert when spend crosses a budget threshold
+            let budget_app_handle = app.handle().clone();
+            std::thread::spawn(move || budget_alerts::run(budget_app_handle));
+
             // Start watching distill directory for changes
             let app_handle = app.handle().clone();
             std::thread::spawn(move || {
@@ -7450,11 +10237,25 @@ pub fn run() {
             list_projects,
             list_sessions,
             get_sessions_usage,
+            get_pricing_table,
+            get_usage_report,
+            export_usage,
+            get_budget_settings,
+            save_budget_settings,
+            get_budget_status,
+            list_subagents,
             list_all_sessions,
             list_all_chats,
             get_session_messages,
+            get_session_messages_page,
+            get_session_transcript,
+            export_session,
+            get_conversation_tree,
+            get_conversation_branch,
             build_search_index,
             search_chats,
+            search_chat_sessions,
+            semantic_search_chats,
             list_local_commands,
             list_local_agents,
             list_local_skills,
@@ -7497,6 +10298,15 @@ pub fn run() {
             open_file_at_line,
             open_session_in_editor,
             reveal_session_file,
+            archive_session,
+            trash_session,
+            restore_session,
+            list_trashed_sessions,
+            get_session_meta,
+            update_session_meta,
+            list_session_tags,
+            get_trash_settings,
+            save_trash_settings,
             reveal_path,
             open_path,
             get_session_file_path,
@@ -7556,6 +10366,7 @@ pub fn run() {
             pty_scrollback,
             pty_purge_scrollback,
             pty_flush_scrollback,
+            open_session_in_panel,
             // Workspace commands
             workspace_load,
             workspace_save,
@@ -0,0 +1,277 @@
+//! Per-model token pricing
+//!
+//! Prices come from the table bundled in pricing.json, overlaid with user overrides
+//! from ~/.lovstudio/lovcode/pricing.json in the same format, where every field is
+//! optional. A model ID is priced by the longest table key it starts with, so
+//! `claude-sonnet-4-5-20250929` uses `claude-sonnet-4-5`; unknown models use `fallback`.
+//! A model priced by a key that isn't its own name, apart from a snapshot date, is
+//! logged once: a newer `claude-opus-4-6` would otherwise quietly get the
+//! `claude-opus-4` price.
+
+use crate::{ModelUsage, SessionUsage};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const BUNDLED_PRICING: &str = include_str!("pricing.json");
+
+/// USD per 1M tokens
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write_5m: f64,
+    pub cache_write_1h: f64,
+    pub cache_read: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingTable {
+    /// Version of the bundled table
+    pub version: u32,
+    pub updated: String,
+    pub fallback: ModelPrice,
+    pub models: BTreeMap<String, ModelPrice>, // model ID prefix -> price
+    /// Whether user overrides were applied
+    #[serde(default)]
+    pub overridden: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PriceOverride {
+    input: Option<f64>,
+    output: Option<f64>,
+    cache_write_5m: Option<f64>,
+    cache_write_1h: Option<f64>,
+    cache_read: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PricingOverrides {
+    fallback: Option<PriceOverride>,
+    models: BTreeMap<String, PriceOverride>,
+}
+
+impl ModelPrice {
+    fn with_override(mut self, o: &PriceOverride) -> Self {
+        self.input = o.input.unwrap_or(self.input);
+        self.output = o.output.unwrap_or(self.output);
+        self.cache_write_5m = o.cache_write_5m.unwrap_or(self.cache_write_5m);
+        self.cache_write_1h = o.cache_write_1h.unwrap_or(self.cache_write_1h);
+        self.cache_read = o.cache_read.unwrap_or(self.cache_read);
+        self
+    }
+
+    pub fn cost(&self, usage: &ModelUsage) -> f64 {
+        let per_m = |tokens: u64, price: f64| tokens as f64 / 1_000_000.0 * price;
+        per_m(usage.input_tokens, self.input)
+            + per_m(usage.output_tokens, self.output)
+            + per_m(usage.cache_creation_5m_tokens, self.cache_write_5m)
+            + per_m(usage.cache_creation_1h_tokens, self.cache_write_1h)
+            + per_m(usage.cache_read_tokens, self.cache_read)
+    }
+}
+
+/// How a model ID was matched to its price
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriceMatch {
+    /// The key is the model ID, possibly without its snapshot date
+    Exact,
+    /// Only a shorter key matched, e.g. `claude-opus-4` for `claude-opus-4-6`
+    Prefix(String),
+    Fallback,
+}
+
+/// Models already logged as priced by a guess
+static GUESSED_MODELS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Whether what follows a key in a model ID is only a snapshot date, as in
+/// `-20250929` or Vertex's `@20250929`
+fn is_snapshot_suffix(suffix: &str) -> bool {
+    if suffix.is_empty() {
+        return true;
+    }
+    let date = match suffix.strip_prefix('-').or_else(|| suffix.strip_prefix('@')) {
+        Some(date) => date,
+        None => return false,
+    };
+    date.len() == 8 && date.chars().all(|c| c.is_ascii_digit())
+}
+
+impl PricingTable {
+    /// Price of a model and how it was matched: by the longest key the ID starts with
+    pub fn lookup(&self, model: &str) -> (ModelPrice, PriceMatch) {
+        let matched = self
+            .models
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len());
+        match matched {
+            Some((prefix, price)) if is_snapshot_suffix(&model[prefix.len()..]) => (*price, PriceMatch::Exact),
+            Some((prefix, price)) => (*price, PriceMatch::Prefix(prefix.clone())),
+            None => (self.fallback, PriceMatch::Fallback),
+        }
+    }
+
+    /// Price of a model. Guessed prices are logged once per model.
+    pub fn price_for(&self, model: &str) -> ModelPrice {
+        let (price, matched) = self.lookup(model);
+        if matched != PriceMatch::Exact {
+            let mut guard = match GUESSED_MODELS.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            if guard.get_or_insert_with(HashSet::new).insert(model.to_string()) {
+                match matched {
+                    PriceMatch::Prefix(prefix) => {
+                        eprintln!("No price for model {}, using the price of {}", model, prefix)
+                    }
+                    _ => eprintln!("No price for model {}, using the fallback price", model),
+                }
+            }
+        }
+        price
+    }
+}
+
+fn get_overrides_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("pricing.json")
+}
+
+fn bundled_table() -> PricingTable {
+    serde_json::from_str(BUNDLED_PRICING).expect("bundled pricing.json is valid")
+}
+
+fn load_table() -> PricingTable {
+    let table = bundled_table();
+    match fs::read_to_string(get_overrides_path()) {
+        Ok(content) => merge_overrides(table, &content),
+        Err(_) => table,
+    }
+}
+
+/// Overlay the user's overrides file content on a table. Invalid overrides are ignored.
+fn merge_overrides(mut table: PricingTable, content: &str) -> PricingTable {
+    let overrides: PricingOverrides = match serde_json::from_str(content) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            eprintln!("Ignoring invalid pricing overrides: {}", e);
+            return table;
+        }
+    };
+
+    if let Some(fallback) = &overrides.fallback {
+        table.fallback = table.fallback.with_override(fallback);
+    }
+    for (prefix, price) in &overrides.models {
+        // New models start from the price they would get without the override
+        let (base, _) = table.lookup(prefix);
+        table.models.insert(prefix.clone(), base.with_override(price));
+    }
+    table.overridden = true;
+    table
+}
+
+/// Loaded table and the overrides mtime it was built with
+static TABLE: Mutex<Option<(u64, Arc<PricingTable>)>> = Mutex::new(None);
+
+/// Effective pricing table, reloaded when the overrides file changes
+pub fn table() -> Arc<PricingTable> {
+    let mtime = crate::file_size_and_mtime(&get_overrides_path())
+        .map(|(_, mtime)| mtime)
+        .unwrap_or(0);
+    let mut guard = match TABLE.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    match guard.as_ref() {
+        Some((loaded_mtime, table)) if *loaded_mtime == mtime => table.clone(),
+        _ => {
+            let table = Arc::new(load_table());
+            *guard = Some((mtime, table.clone()));
+            table
+        }
+    }
+}
+
+/// Fill in the cost of every model and the session total
+pub fn apply_costs(usage: &mut SessionUsage) {
+    let table = table();
+    usage.cost_usd = 0.0;
+    for (model, model_usage) in usage.by_model.iter_mut() {
+        model_usage.cost_usd = table.price_for(model).cost(model_usage);
+        usage.cost_usd += model_usage.cost_usd;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_for_uses_longest_prefix() {
+        let table = bundled_table();
+        assert_eq!(table.price_for("claude-opus-4-5-20251101").input, 5.0);
+        assert_eq!(table.price_for("claude-opus-4-20250514").input, 15.0);
+        assert_eq!(table.price_for("claude-sonnet-4-5-20250929").input, 3.0);
+        assert_eq!(table.price_for("claude-3-5-haiku-20241022").input, 0.8);
+        assert_eq!(table.price_for("claude-3-haiku-20240307").input, 0.25);
+        assert_eq!(table.price_for("gpt-5").input, table.fallback.input);
+    }
+
+    #[test]
+    fn lookup_flags_non_exact_matches() {
+        let table = bundled_table();
+        assert_eq!(table.lookup("claude-opus-4-5").1, PriceMatch::Exact);
+        assert_eq!(table.lookup("claude-opus-4-5-20251101").1, PriceMatch::Exact);
+        assert_eq!(table.lookup("claude-opus-4-5@20251101").1, PriceMatch::Exact);
+        assert_eq!(
+            table.lookup("claude-opus-4-6").1,
+            PriceMatch::Prefix("claude-opus-4".to_string())
+        );
+        assert_eq!(
+            table.lookup("claude-opus-4-6-20260101").1,
+            PriceMatch::Prefix("claude-opus-4".to_string())
+        );
+        assert_eq!(table.lookup("gpt-5").1, PriceMatch::Fallback);
+    }
+
+    #[test]
+    fn overrides_merge_field_by_field() {
+        let table = merge_overrides(
+            bundled_table(),
+            r#"{
+                "fallback": { "output": 20.0 },
+                "models": {
+                    "claude-sonnet-4-5": { "input": 2.5 },
+                    "claude-opus-4-6": { "input": 6.0, "output": 30.0 }
+                }
+            }"#,
+        );
+        assert!(table.overridden);
+
+        // Fields left out keep the bundled price
+        assert_eq!(table.fallback.output, 20.0);
+        assert_eq!(table.fallback.input, 3.0);
+        let sonnet = table.price_for("claude-sonnet-4-5-20250929");
+        assert_eq!(sonnet.input, 2.5);
+        assert_eq!(sonnet.output, 15.0);
+
+        // A new key starts from the price its longest prefix had
+        let opus = table.price_for("claude-opus-4-6");
+        assert_eq!((opus.input, opus.output, opus.cache_read), (6.0, 30.0, 1.5));
+        assert_eq!(table.lookup("claude-opus-4-6").1, PriceMatch::Exact);
+        assert_eq!(table.price_for("claude-opus-4-20250514").input, 15.0);
+    }
+
+    #[test]
+    fn invalid_overrides_are_ignored() {
+        let table = merge_overrides(bundled_table(), "{ not json");
+        assert!(!table.overridden);
+        assert_eq!(table.price_for("claude-sonnet-4-5").input, 3.0);
+    }
+}
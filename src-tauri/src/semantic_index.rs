@@ -0,0 +1,429 @@
+//! Semantic search over session messages
+//!
+//! Messages are split into chunks and embedded on CPU with a local BERT-style
+//! sentence-transformer (config.json, tokenizer.json, model.safetensors) placed in
+//! ~/.lovstudio/lovcode/models/embedding. Vectors are stored per session in a sidecar
+//! directory next to the search index and kept in sync by `build_search_index`.
+//! Embedding requires the `semantic-search` cargo feature; without it nothing is embedded.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Characters per chunk, and characters shared by consecutive chunks
+const CHUNK_CHARS: usize = 512;
+const CHUNK_OVERLAP_CHARS: usize = 64;
+
+/// Chunks embedded per model call
+const EMBED_BATCH_SIZE: usize = 16;
+
+/// Header of a session vector file: magic, format version, vector dimension
+const VECTOR_FILE_MAGIC: &[u8; 4] = b"LVEC";
+const VECTOR_FILE_VERSION: u32 = 1;
+
+/// A newly indexed message to embed
+pub struct MessageText {
+    pub uuid: String,
+    pub text: String,
+}
+
+/// Embedded chunks of one session
+struct SessionVectors {
+    project_id: String,
+    session_id: String,
+    records: Vec<(String, Vec<f32>)>, // message uuid -> chunk vector
+}
+
+/// Embedding model loaded for a model fingerprint, or why it failed to load. A failed
+/// load is not retried until the model files change.
+type LoadedEmbedder = (String, Result<model::Embedder, String>);
+static EMBEDDER: LazyLock<Mutex<Option<LoadedEmbedder>>> = LazyLock::new(|| Mutex::new(None));
+
+/// All session vectors, loaded on first query and dropped on every write
+static VECTOR_CACHE: Mutex<Option<Vec<SessionVectors>>> = Mutex::new(None);
+
+pub fn get_model_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".lovstudio")
+        .join("lovcode")
+        .join("models")
+        .join("embedding")
+}
+
+fn get_vectors_dir() -> PathBuf {
+    crate::get_index_dir().join("embeddings")
+}
+
+fn get_fingerprint_path() -> PathBuf {
+    get_vectors_dir().join("model.txt")
+}
+
+fn get_session_vectors_path(project_id: &str, session_id: &str) -> PathBuf {
+    get_vectors_dir()
+        .join(project_id)
+        .join(format!("{}.vec", session_id))
+}
+
+/// Identifies the model files, so vectors from another model are never mixed in
+fn model_fingerprint() -> Option<String> {
+    let dir = get_model_dir();
+    let config = fs::read_to_string(dir.join("config.json")).ok()?;
+    let weights = fs::metadata(dir.join("model.safetensors")).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&config).ok()?;
+    Some(format!(
+        "{}:{}:{}",
+        config.get("_name_or_path").and_then(|v| v.as_str()).unwrap_or("bert"),
+        config.get("hidden_size").and_then(|v| v.as_u64()).unwrap_or(0),
+        weights.len()
+    ))
+}
+
+/// Run `f` with the embedding model, loading it on first use and whenever it changes
+fn with_embedder<T>(f: impl FnOnce(&model::Embedder) -> Result<T, String>) -> Result<T, String> {
+    let fingerprint = model_fingerprint().ok_or("No embedding model found")?;
+    let mut guard = EMBEDDER.lock().map_err(|e| e.to_string())?;
+    let loaded = match guard.take() {
+        Some((loaded_fingerprint, embedder)) if loaded_fingerprint == fingerprint => (fingerprint, embedder),
+        _ => {
+            let embedder = model::Embedder::load(&get_model_dir());
+            (fingerprint, embedder)
+        }
+    };
+    let (_, embedder) = guard.insert(loaded);
+    match embedder {
+        Ok(embedder) => f(embedder),
+        Err(e) => Err(format!("Embedding model failed to load: {}", e)),
+    }
+}
+
+/// Whether messages can be embedded (feature compiled in and model present)
+pub fn is_enabled() -> bool {
+    cfg!(feature = "semantic-search") && model_fingerprint().is_some()
+}
+
+/// Whether stored vectors were made by another model (or never made) and need a full pass
+pub fn needs_rebuild() -> bool {
+    let stored = fs::read_to_string(get_fingerprint_path()).ok();
+    is_enabled() && stored != model_fingerprint()
+}
+
+/// Drop every stored vector, e.g. before the search index is rebuilt from scratch
+pub fn clear() -> Result<(), String> {
+    let dir = get_vectors_dir();
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    invalidate_cache();
+
+    if let Some(fingerprint) = model_fingerprint().filter(|_| is_enabled()) {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        fs::write(get_fingerprint_path(), fingerprint).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn invalidate_cache() {
+    if let Ok(mut cache) = VECTOR_CACHE.lock() {
+        *cache = None;
+    }
+}
+
+/// Remove the vectors of a session
+pub fn remove_session(project_id: &str, session_id: &str) {
+    let path = get_session_vectors_path(project_id, session_id);
+    if path.exists() {
+        let _ = fs::remove_file(path);
+        invalidate_cache();
+    }
+}
+
+/// Split text into overlapping chunks of roughly `CHUNK_CHARS` characters
+pub fn chunk_text(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    if chars.is_empty() {
+        return Vec::new();
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + CHUNK_CHARS).min(chars.len());
+        chunks.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP_CHARS;
+    }
+    chunks
+}
+
+/// Embed the messages of a session and append them to its vector file
+pub fn add_messages(project_id: &str, session_id: &str, messages: &[MessageText]) -> Result<(), String> {
+    let chunks: Vec<(&str, String)> = messages
+        .iter()
+        .flat_map(|m| chunk_text(&m.text).into_iter().map(move |c| (m.uuid.as_str(), c)))
+        .collect();
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    let mut records = Vec::with_capacity(chunks.len());
+    for batch in chunks.chunks(EMBED_BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = with_embedder(|embedder| embedder.embed(&texts))?;
+        for ((uuid, _), vector) in batch.iter().zip(vectors) {
+            records.push((uuid.to_string(), vector));
+        }
+    }
+
+    append_vectors(&get_session_vectors_path(project_id, session_id), &records)?;
+    invalidate_cache();
+    Ok(())
+}
+
+/// Embed a search query
+pub fn embed_query(query: &str) -> Result<Vec<f32>, String> {
+    if !cfg!(feature = "semantic-search") {
+        return Err("Semantic search is not available in this build".to_string());
+    }
+    if model_fingerprint().is_none() {
+        return Err(format!(
+            "Embedding model not found. Place config.json, tokenizer.json and model.safetensors in {}",
+            get_model_dir().display()
+        ));
+    }
+    let mut vectors = with_embedder(|embedder| embedder.embed(&[query.to_string()]))?;
+    vectors.pop().ok_or_else(|| "Failed to embed query".to_string())
+}
+
+/// Cosine similarity of the query to every embedded message, keeping the best chunk per message.
+/// Vectors are normalized when embedded, so the dot product is the cosine.
+pub fn similarities(
+    query: &[f32],
+    project_id: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<HashMap<String, f32>, String> {
+    let mut cache = VECTOR_CACHE.lock().map_err(|e| e.to_string())?;
+    if cache.is_none() {
+        *cache = Some(load_all_vectors());
+    }
+
+    let mut scores: HashMap<String, f32> = HashMap::new();
+    for session in cache.as_ref().unwrap() {
+        if project_id.is_some_and(|id| id != session.project_id)
+            || session_id.is_some_and(|id| id != session.session_id)
+        {
+            continue;
+        }
+        for (uuid, vector) in &session.records {
+            if vector.len() != query.len() {
+                continue;
+            }
+            let similarity: f32 = vector.iter().zip(query).map(|(a, b)| a * b).sum();
+            scores
+                .entry(uuid.clone())
+                .and_modify(|best| *best = best.max(similarity))
+                .or_insert(similarity);
+        }
+    }
+
+    Ok(scores)
+}
+
+fn append_vectors(path: &Path, records: &[(String, Vec<f32>)]) -> Result<(), String> {
+    let dim = records.first().map(|(_, v)| v.len()).unwrap_or(0) as u32;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    // Start a new file if there is none or it holds vectors of another dimension
+    let existing_dim = read_vector_header(path).ok().map(|(_, dim)| dim);
+    let is_new = existing_dim != Some(dim);
+
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(!is_new)
+        .truncate(is_new)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+
+    let mut write = |bytes: &[u8]| writer.write_all(bytes).map_err(|e| e.to_string());
+    if is_new {
+        write(VECTOR_FILE_MAGIC)?;
+        write(&VECTOR_FILE_VERSION.to_le_bytes())?;
+        write(&dim.to_le_bytes())?;
+    }
+    for (uuid, vector) in records {
+        write(&(uuid.len() as u16).to_le_bytes())?;
+        write(uuid.as_bytes())?;
+        for value in vector {
+            write(&value.to_le_bytes())?;
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+fn read_vector_header(path: &Path) -> std::io::Result<(BufReader<fs::File>, u32)> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if &header[..4] != VECTOR_FILE_MAGIC || version != VECTOR_FILE_VERSION {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "unknown vector file"));
+    }
+    Ok((reader, u32::from_le_bytes(header[8..12].try_into().unwrap())))
+}
+
+fn read_session_vectors(path: &Path) -> std::io::Result<Vec<(String, Vec<f32>)>> {
+    let (mut reader, dim) = read_vector_header(path)?;
+    let mut records = Vec::new();
+    let mut len_buf = [0u8; 2];
+    let mut vector_buf = vec![0u8; dim as usize * 4];
+
+    // A truncated trailing record (interrupted write) ends the file
+    while reader.read_exact(&mut len_buf).is_ok() {
+        let mut uuid = vec![0u8; u16::from_le_bytes(len_buf) as usize];
+        if reader.read_exact(&mut uuid).is_err() || reader.read_exact(&mut vector_buf).is_err() {
+            break;
+        }
+        let vector = vector_buf
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        records.push((String::from_utf8_lossy(&uuid).to_string(), vector));
+    }
+
+    Ok(records)
+}
+
+fn load_all_vectors() -> Vec<SessionVectors> {
+    let mut sessions = Vec::new();
+    let project_dirs = fs::read_dir(get_vectors_dir()).into_iter().flatten().flatten();
+
+    for project_entry in project_dirs {
+        let project_dir = project_entry.path();
+        if !project_dir.is_dir() {
+            continue;
+        }
+        let project_id = project_entry.file_name().to_string_lossy().to_string();
+
+        for entry in fs::read_dir(&project_dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "vec") {
+                if let Ok(records) = read_session_vectors(&path) {
+                    sessions.push(SessionVectors {
+                        project_id: project_id.clone(),
+                        session_id: path.file_stem().unwrap().to_string_lossy().to_string(),
+                        records,
+                    });
+                }
+            }
+        }
+    }
+
+    sessions
+}
+
+#[cfg(feature = "semantic-search")]
+mod model {
+    use candle_core::{Device, Tensor};
+    use candle_nn::VarBuilder;
+    use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+    use std::fs;
+    use std::path::Path;
+    use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+    /// Longer chunks are truncated by the tokenizer
+    const MAX_TOKENS: usize = 256;
+
+    pub struct Embedder {
+        model: BertModel,
+        tokenizer: Tokenizer,
+        device: Device,
+    }
+
+    impl Embedder {
+        pub fn load(dir: &Path) -> Result<Self, String> {
+            let device = Device::Cpu;
+
+            let config = fs::read_to_string(dir.join("config.json"))
+                .map_err(|e| format!("Failed to read model config: {}", e))?;
+            let config: Config = serde_json::from_str(&config)
+                .map_err(|e| format!("Failed to parse model config: {}", e))?;
+
+            let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))
+                .map_err(|e| format!("Failed to load tokenizer: {}", e))?;
+            tokenizer.with_padding(Some(PaddingParams::default()));
+            tokenizer
+                .with_truncation(Some(TruncationParams {
+                    max_length: MAX_TOKENS,
+                    ..Default::default()
+                }))
+                .map_err(|e| e.to_string())?;
+
+            // Safety: the weights file is only read, and is not expected to change while mapped
+            let vb = unsafe {
+                VarBuilder::from_mmaped_safetensors(&[dir.join("model.safetensors")], DTYPE, &device)
+            }
+            .map_err(|e| format!("Failed to load model weights: {}", e))?;
+            let model = BertModel::load(vb, &config).map_err(|e| format!("Failed to load model: {}", e))?;
+
+            Ok(Self {
+                model,
+                tokenizer,
+                device,
+            })
+        }
+
+        /// Mean-pooled, L2-normalized sentence embeddings
+        pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+            self.embed_batch(texts).map_err(|e| format!("Embedding failed: {}", e))
+        }
+
+        fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error + Send + Sync>> {
+            let encodings = self.tokenizer.encode_batch(texts.to_vec(), true)?;
+
+            let ids = encodings
+                .iter()
+                .map(|e| Tensor::new(e.get_ids(), &self.device))
+                .collect::<Result<Vec<_>, _>>()?;
+            let masks = encodings
+                .iter()
+                .map(|e| Tensor::new(e.get_attention_mask(), &self.device))
+                .collect::<Result<Vec<_>, _>>()?;
+            let input_ids = Tensor::stack(&ids, 0)?;
+            let attention_mask = Tensor::stack(&masks, 0)?;
+            let token_type_ids = input_ids.zeros_like()?;
+
+            // [batch, tokens, hidden] -> mean over non-padding tokens -> [batch, hidden]
+            let output = self.model.forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+            let mask = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+            let pooled = output.broadcast_mul(&mask)?.sum(1)?.broadcast_div(&mask.sum(1)?)?;
+            let norm = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+
+            Ok(pooled.broadcast_div(&norm)?.to_vec2::<f32>()?)
+        }
+    }
+}
+
+#[cfg(not(feature = "semantic-search"))]
+mod model {
+    use std::path::Path;
+
+    pub struct Embedder;
+
+    impl Embedder {
+        pub fn load(_dir: &Path) -> Result<Self, String> {
+            Err("Semantic search is not available in this build".to_string())
+        }
+
+        pub fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+            Err("Semantic search is not available in this build".to_string())
+        }
+    }
+}
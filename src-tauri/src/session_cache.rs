@@ -0,0 +1,502 @@
+//! Persistent metadata cache for session listings
+//!
+//! Listing sessions used to mean reading every session file and all of history.jsonl
+//! on each call. The cache keeps what the listings need per session file (summary,
+//! message count, first/last timestamp, usage), the history.jsonl index and resolved
+//! project paths in ~/.lovstudio/lovcode/session-cache.json, along with the sessions
+//! read from other agents' transcripts. Entries are keyed by path
+//! and checked against size and mtime, so only files that changed are read again.
+//!
+//! Usage is kept per assistant message, keyed by `message.id` and `requestId`. Claude
+//! Code writes a message once per streamed content block and resumed sessions replay
+//! the messages of the session they continue, so usage is counted once per key: the
+//! last copy within a file, and across files the one that started first. The messages
+//! of each file are stored apart from the listing metadata, under usage-cache/ in the
+//! layout of ~/.claude/projects, so a growing session only rewrites its own messages.
+
+use crate::{ModelUsage, RawLine, Session, SessionUsage};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Bump when `CachedSessionFile` changes meaning, to read every file again
+const CACHE_VERSION: u32 = 6;
+
+/// Lines read from the head of a session for its summary
+const SUMMARY_HEAD_LINES: usize = 20;
+
+/// Usage of one assistant message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMessage {
+    pub key: Option<String>, // "<message.id>:<requestId>"
+    pub timestamp: Option<i64>, // unix seconds
+    pub model: String,
+    pub usage: ModelUsage,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedSessionFile {
+    pub size: u64,
+    pub mtime: u64, // ms
+    pub summary: Option<String>,
+    pub message_count: usize,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// Totalled and priced on lookup, so pricing changes apply at once
+    #[serde(skip)]
+    pub usage: SessionUsage,
+    /// Assistant messages with usage, one per key. Stored in the file's usage file.
+    #[serde(skip)]
+    pub messages: Vec<CachedMessage>,
+    /// Whether `messages` are in memory yet, they are read on first lookup
+    #[serde(skip)]
+    messages_loaded: bool,
+}
+
+/// Contents of a usage file, checked against the session file like its metadata
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedUsageFile {
+    size: u64,
+    mtime: u64, // ms
+    messages: Vec<CachedMessage>,
+}
+
+/// Latest history.jsonl entry of each session
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedHistory {
+    /// Byte offset just past the last parsed line
+    offset: u64,
+    /// project_id -> session_id -> (timestamp ms, display)
+    sessions: HashMap<String, HashMap<String, (u64, Option<String>)>>,
+}
+
+/// Project path resolved from session `cwd`s. Projects without any recorded cwd are
+/// re-checked once their directory changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedProjectPath {
+    pub path: Option<String>,
+    pub dir_mtime: u64, // ms
+}
+
+/// Session read from another agent's transcript, without its messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedImportedSession {
+    size: u64,
+    mtime: u64,               // ms
+    session: Option<Session>, // None when the transcript could not be read
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionCache {
+    version: u32,
+    files: HashMap<String, CachedSessionFile>, // session file path -> metadata
+    history: CachedHistory,
+    project_paths: HashMap<String, CachedProjectPath>, // project_id -> path
+    #[serde(default)]
+    imported: HashMap<String, CachedImportedSession>, // transcript path -> session
+    #[serde(skip)]
+    dirty: bool,
+}
+
+static CACHE: Mutex<Option<SessionCache>> = Mutex::new(None);
+
+fn get_cache_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("session-cache.json")
+}
+
+fn get_usage_dir() -> PathBuf {
+    crate::get_lovstudio_dir().join("usage-cache")
+}
+
+/// Usage file of a session file: its path below ~/.claude/projects, as .json
+fn get_usage_path(path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(crate::get_claude_dir().join("projects")).ok()?;
+    Some(get_usage_dir().join(relative).with_extension("json"))
+}
+
+fn load_cache() -> SessionCache {
+    let cache = fs::read_to_string(get_cache_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<SessionCache>(&content).ok())
+        .filter(|cache| cache.version == CACHE_VERSION);
+    cache.unwrap_or_else(|| {
+        // Usage files of an older or lost cache are never looked at again
+        let _ = fs::remove_dir_all(get_usage_dir());
+        SessionCache {
+            version: CACHE_VERSION,
+            ..Default::default()
+        }
+    })
+}
+
+fn save_cache(cache: &SessionCache) -> Result<(), String> {
+    let path = get_cache_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string(cache).map_err(|e| e.to_string())?;
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+}
+
+/// Run `f` on the cache. With `save`, changes reported by `f` are persisted right away,
+/// otherwise they wait for the next `flush`.
+fn with_cache<T>(save: bool, f: impl FnOnce(&mut SessionCache) -> (T, bool)) -> T {
+    let mut guard = match CACHE.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let cache = guard.get_or_insert_with(load_cache);
+    let (value, changed) = f(cache);
+    cache.dirty |= changed;
+    if save && cache.dirty {
+        // Entries of removed files are dropped whenever the cache is written
+        cache.files.retain(|path, _| {
+            let exists = Path::new(path).exists();
+            if let (false, Some(usage_path)) = (exists, get_usage_path(Path::new(path))) {
+                let _ = fs::remove_file(usage_path);
+            }
+            exists
+        });
+        cache.imported.retain(|path, _| Path::new(path).exists());
+        match save_cache(cache) {
+            Ok(()) => cache.dirty = false,
+            Err(e) => eprintln!("Failed to save session cache: {}", e),
+        }
+    }
+    value
+}
+
+/// Persist changes made without saving
+pub fn flush() {
+    with_cache(true, |_| ((), false))
+}
+
+/// Messages stored for a session file, None unless they match its size and mtime
+fn read_usage_file(path: &Path, size: u64, mtime: u64) -> Option<Vec<CachedMessage>> {
+    let content = fs::read_to_string(get_usage_path(path)?).ok()?;
+    let usage: CachedUsageFile = serde_json::from_str(&content).ok()?;
+    (usage.size == size && usage.mtime == mtime).then_some(usage.messages)
+}
+
+fn write_usage_file(path: &Path, entry: &CachedSessionFile) -> Result<(), String> {
+    let usage_path = match get_usage_path(path) {
+        Some(usage_path) => usage_path,
+        None => return Ok(()),
+    };
+    if let Some(parent) = usage_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = usage_path.with_extension("json.tmp");
+    let usage = CachedUsageFile {
+        size: entry.size,
+        mtime: entry.mtime,
+        messages: entry.messages.clone(),
+    };
+    let content = serde_json::to_string(&usage).map_err(|e| e.to_string())?;
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, &usage_path).map_err(|e| e.to_string())
+}
+
+/// Read everything the listings need from a session file in one pass
+fn scan_session_file(path: &Path, size: u64, mtime: u64) -> CachedSessionFile {
+    let (summary, _) = crate::read_session_head(path, SUMMARY_HEAD_LINES);
+    let mut entry = CachedSessionFile {
+        size,
+        mtime,
+        summary,
+        messages_loaded: true,
+        ..Default::default()
+    };
+    // Position of each message key in `entry.messages`
+    let mut message_index: HashMap<String, usize> = HashMap::new();
+
+    let _ = crate::for_each_complete_line(path, 0, |line| {
+        let parsed = match serde_json::from_str::<RawLine>(line) {
+            Ok(parsed) => parsed,
+            Err(_) => return true,
+        };
+        let line_type = parsed.line_type.as_deref();
+        if line_type != Some("user") && line_type != Some("assistant") {
+            return true;
+        }
+
+        entry.message_count += 1;
+        if let Some(timestamp) = &parsed.timestamp {
+            if entry.first_timestamp.is_none() {
+                entry.first_timestamp = Some(timestamp.clone());
+            }
+            entry.last_timestamp = Some(timestamp.clone());
+        }
+        // Only assistant messages have usage data
+        if line_type == Some("assistant") {
+            if let Some(message) = &parsed.message {
+                if let Some(u) = &message.usage {
+                    let key = match (&message.id, &parsed.request_id) {
+                        (Some(id), Some(request_id)) => Some(format!("{}:{}", id, request_id)),
+                        _ => None,
+                    };
+                    let cached = CachedMessage {
+                        key: key.clone(),
+                        timestamp: parsed
+                            .timestamp
+                            .as_deref()
+                            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+                            .map(|dt| dt.timestamp()),
+                        model: message.model.clone().unwrap_or_else(|| "unknown".to_string()),
+                        usage: ModelUsage::from_raw(u),
+                    };
+                    // Streamed chunks repeat the message, the last one has the final counts
+                    match key.as_ref().and_then(|key| message_index.get(key)) {
+                        Some(&idx) => entry.messages[idx] = cached,
+                        None => {
+                            if let Some(key) = key {
+                                message_index.insert(key, entry.messages.len());
+                            }
+                            entry.messages.push(cached);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    });
+
+    entry
+}
+
+/// Metadata of session files, in the order given. Files that changed since they were
+/// cached are read again, outside of the cache lock; missing files get an empty entry.
+/// Only the usage files of changed files are written. Messages are deduplicated across
+/// the given files, so pass a session together with its resumed copies.
+pub fn lookup_files(paths: &[PathBuf]) -> Vec<CachedSessionFile> {
+    let stamps: Vec<Option<(u64, u64)>> = paths.iter().map(|path| crate::file_size_and_mtime(path).ok()).collect();
+    let mut cached: Vec<Option<CachedSessionFile>> = with_cache(false, |cache| {
+        let cached = paths
+            .iter()
+            .zip(&stamps)
+            .map(|(path, stamp)| {
+                let (size, mtime) = (*stamp)?;
+                cache
+                    .files
+                    .get(path.to_string_lossy().as_ref())
+                    .filter(|cached| cached.size == size && cached.mtime == mtime)
+                    .cloned()
+            })
+            .collect();
+        (cached, false)
+    });
+
+    let mut fresh = Vec::new();
+    let mut loaded = Vec::new();
+    for ((path, stamp), entry) in paths.iter().zip(&stamps).zip(cached.iter_mut()) {
+        let (size, mtime) = match *stamp {
+            Some(stamp) => stamp,
+            None => continue,
+        };
+        let key = path.to_string_lossy().to_string();
+        match entry {
+            Some(hit) if hit.messages_loaded => continue,
+            Some(hit) => {
+                if let Some(messages) = read_usage_file(path, size, mtime) {
+                    hit.messages = messages;
+                    hit.messages_loaded = true;
+                    loaded.push((key, hit.clone()));
+                    continue;
+                }
+            }
+            None => {}
+        }
+        let scanned = scan_session_file(path, size, mtime);
+        if let Err(e) = write_usage_file(path, &scanned) {
+            eprintln!("Failed to save session usage cache: {}", e);
+        }
+        fresh.push((key, scanned.clone()));
+        *entry = Some(scanned);
+    }
+    if !fresh.is_empty() || !loaded.is_empty() {
+        // Listing metadata is saved when a file changed, messages read from disk only stay in memory
+        with_cache(!fresh.is_empty(), |cache| {
+            for (key, entry) in loaded {
+                if let Some(current) = cache.files.get_mut(&key) {
+                    if current.size == entry.size && current.mtime == entry.mtime {
+                        *current = entry;
+                    }
+                }
+            }
+            let changed = !fresh.is_empty();
+            cache.files.extend(fresh);
+            ((), changed)
+        });
+    }
+
+    let mut entries: Vec<CachedSessionFile> = cached.into_iter().map(Option::unwrap_or_default).collect();
+
+    dedupe_messages(paths, &mut entries);
+    for entry in entries.iter_mut() {
+        for message in &entry.messages {
+            crate::add_model_usage(&mut entry.usage, &message.model, &message.usage);
+        }
+        crate::pricing::apply_costs(&mut entry.usage);
+    }
+    entries
+}
+
+/// Drop messages another of the files already counts. A message in several files
+/// counts toward the one that started first, i.e. the session that was resumed.
+/// Replayed lines keep their timestamps, so a resumed copy usually starts at the same
+/// time as the original; ties go to the file last written first, as the original stops
+/// growing once it is resumed.
+fn dedupe_messages(paths: &[PathBuf], entries: &mut [CachedSessionFile]) {
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by(|&a, &b| {
+        entries[a]
+            .first_timestamp
+            .cmp(&entries[b].first_timestamp)
+            .then_with(|| entries[a].mtime.cmp(&entries[b].mtime))
+            .then_with(|| paths[a].cmp(&paths[b]))
+    });
+    let mut seen: HashSet<String> = HashSet::new();
+    for idx in order {
+        entries[idx].messages.retain(|message| match &message.key {
+            Some(key) => seen.insert(key.clone()),
+            None => true,
+        });
+    }
+}
+
+/// Sessions recorded in history.jsonl with their latest timestamp and display text.
+/// Only lines appended since the last call are parsed.
+pub fn history_index() -> HashMap<(String, String), (u64, Option<String>)> {
+    let history_path = crate::get_claude_dir().join("history.jsonl");
+    let size = fs::metadata(&history_path).map(|m| m.len()).unwrap_or(0);
+
+    with_cache(true, |cache| {
+        let history = &mut cache.history;
+        let mut changed = false;
+        // Rewritten or truncated: start over
+        if size < history.offset {
+            *history = CachedHistory::default();
+            changed = true;
+        }
+        if size > history.offset {
+            let sessions = &mut history.sessions;
+            let parsed = crate::for_each_complete_line(&history_path, history.offset, |line| {
+                let entry = match serde_json::from_str::<crate::HistoryEntry>(line) {
+                    Ok(entry) => entry,
+                    Err(_) => return true,
+                };
+                if let (Some(session_id), Some(project), Some(timestamp)) =
+                    (entry.session_id, entry.project, entry.timestamp)
+                {
+                    let project_id = crate::encode_project_path(&project);
+                    // Keep the latest timestamp and display for each session
+                    sessions
+                        .entry(project_id)
+                        .or_default()
+                        .entry(session_id)
+                        .and_modify(|(ts, display)| {
+                            if timestamp > *ts {
+                                *ts = timestamp;
+                                *display = entry.display.clone();
+                            }
+                        })
+                        .or_insert((timestamp, entry.display));
+                }
+                true
+            });
+            if let Ok(offset) = parsed {
+                changed |= offset != history.offset;
+                history.offset = offset;
+            }
+        }
+
+        let index = history
+            .sessions
+            .iter()
+            .flat_map(|(project_id, sessions)| {
+                sessions
+                    .iter()
+                    .map(move |(session_id, value)| ((project_id.clone(), session_id.clone()), value.clone()))
+            })
+            .collect();
+        (index, changed)
+    })
+}
+
+pub fn project_path(project_id: &str) -> Option<CachedProjectPath> {
+    with_cache(false, |cache| (cache.project_paths.get(project_id).cloned(), false))
+}
+
+/// Remember a resolved project path. Saved with the next `flush` or file lookup.
+pub fn set_project_path(project_id: &str, resolved: CachedProjectPath) {
+    with_cache(false, |cache| {
+        cache.project_paths.insert(project_id.to_string(), resolved);
+        ((), true)
+    })
+}
+
+/// Transcript of another agent's session, as recorded by the last `lookup_imported`
+pub fn imported_session_path(source: &str, session_id: &str) -> Option<PathBuf> {
+    with_cache(false, |cache| {
+        let path = cache
+            .imported
+            .iter()
+            .find(|(_, cached)| {
+                cached
+                    .session
+                    .as_ref()
+                    .is_some_and(|session| session.source == source && session.id == session_id)
+            })
+            .map(|(path, _)| PathBuf::from(path));
+        (path, false)
+    })
+}
+
+/// Sessions of other agents' transcripts, in the order given. Changed files are read
+/// with `read`, outside of the cache lock; unreadable ones are skipped.
+pub fn lookup_imported(paths: &[PathBuf], read: impl Fn(&Path) -> Option<Session>) -> Vec<Session> {
+    let stamps: Vec<Option<(u64, u64)>> = paths.iter().map(|path| crate::file_size_and_mtime(path).ok()).collect();
+    let mut sessions: Vec<Option<Option<Session>>> = with_cache(false, |cache| {
+        let cached = paths
+            .iter()
+            .zip(&stamps)
+            .map(|(path, stamp)| {
+                let (size, mtime) = (*stamp)?;
+                cache
+                    .imported
+                    .get(path.to_string_lossy().as_ref())
+                    .filter(|cached| cached.size == size && cached.mtime == mtime)
+                    .map(|cached| cached.session.clone())
+            })
+            .collect();
+        (cached, false)
+    });
+
+    let mut fresh = Vec::new();
+    for ((path, stamp), session) in paths.iter().zip(&stamps).zip(sessions.iter_mut()) {
+        if let (Some((size, mtime)), None) = (*stamp, &session) {
+            let read_session = read(path);
+            fresh.push((
+                path.to_string_lossy().to_string(),
+                CachedImportedSession {
+                    size,
+                    mtime,
+                    session: read_session.clone(),
+                },
+            ));
+            *session = Some(read_session);
+        }
+    }
+    if !fresh.is_empty() {
+        with_cache(true, |cache| {
+            cache.imported.extend(fresh);
+            ((), true)
+        });
+    }
+
+    sessions.into_iter().flatten().flatten().collect()
+}
@@ -0,0 +1,316 @@
+//! Session export
+//!
+//! Renders a session transcript as self-contained Markdown, standalone HTML with
+//! collapsible tool calls, or normalized JSON, optionally redacting secrets and
+//! home-directory paths first.
+
+use crate::{ContentBlock, TranscriptMessage};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub redact_secrets: bool,
+    pub redact_home: bool, // home directory -> ~
+    pub exclude_thinking: bool,
+    pub exclude_tools: bool,
+    pub include_meta: bool, // expanded slash command prompts
+}
+
+/// Session as written by the JSON export
+#[derive(Debug, Serialize)]
+pub struct ExportedSession<'a> {
+    pub session_id: &'a str,
+    pub project_id: &'a str,
+    pub project_path: &'a str,
+    pub summary: Option<&'a str>,
+    pub exported_at: String,
+    pub messages: &'a [TranscriptMessage],
+}
+
+/// Session details shown in the export header
+pub struct SessionInfo<'a> {
+    pub session_id: &'a str,
+    pub project_id: &'a str,
+    pub project_path: &'a str,
+    pub summary: Option<&'a str>,
+}
+
+pub fn export(
+    info: &SessionInfo,
+    mut messages: Vec<TranscriptMessage>,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<String, String> {
+    messages.retain(|m| options.include_meta || !m.is_meta);
+    for message in &mut messages {
+        message.blocks.retain(|block| match block {
+            ContentBlock::Thinking { .. } => !options.exclude_thinking,
+            ContentBlock::ToolUse { .. } | ContentBlock::ToolResult { .. } => !options.exclude_tools,
+            _ => true,
+        });
+    }
+    messages.retain(|m| !m.blocks.is_empty());
+
+    // Only whole path components: with home /home/al, /home/alice stays as is
+    let home = dirs::home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .filter(|h| options.redact_home && h.len() > 1)
+        .and_then(|h| regex::Regex::new(&format!(r"{}([^\w.-]|$)", regex::escape(&h))).ok());
+    let redact = |text: &str| -> String {
+        let text = if options.redact_secrets {
+            crate::diagnostics::redact_secrets(text)
+        } else {
+            text.to_string()
+        };
+        match &home {
+            Some(home) => home.replace_all(&text, "~$1").into_owned(),
+            None => text,
+        }
+    };
+    for block in messages.iter_mut().flat_map(|m| m.blocks.iter_mut()) {
+        redact_block(block, &redact);
+    }
+    let project_path = redact(info.project_path);
+    let summary = info.summary.map(redact);
+    let info = SessionInfo {
+        project_path: &project_path,
+        summary: summary.as_deref(),
+        ..*info
+    };
+
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(&info, &messages)),
+        ExportFormat::Html => Ok(render_html(&info, &messages)),
+        ExportFormat::Json => serde_json::to_string_pretty(&ExportedSession {
+            session_id: info.session_id,
+            project_id: info.project_id,
+            project_path: info.project_path,
+            summary: info.summary,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            messages: &messages,
+        })
+        .map_err(|e| e.to_string()),
+    }
+}
+
+fn redact_block(block: &mut ContentBlock, redact: &impl Fn(&str) -> String) {
+    match block {
+        ContentBlock::Text { text } => *text = redact(text),
+        ContentBlock::Thinking { thinking } => *thinking = redact(thinking),
+        ContentBlock::ToolUse { input, .. } => redact_value(input, redact),
+        ContentBlock::ToolResult { content, .. } => {
+            content.iter_mut().for_each(|b| redact_block(b, redact));
+        }
+        ContentBlock::Image { .. } => {}
+    }
+}
+
+fn redact_value(value: &mut Value, redact: &impl Fn(&str) -> String) {
+    match value {
+        Value::String(s) => *s = redact(s),
+        Value::Array(items) => items.iter_mut().for_each(|v| redact_value(v, redact)),
+        Value::Object(map) => map.values_mut().for_each(|v| redact_value(v, redact)),
+        _ => {}
+    }
+}
+
+fn role_title(role: &str) -> &str {
+    match role {
+        "user" => "User",
+        "assistant" => "Assistant",
+        other => other,
+    }
+}
+
+fn format_tool_input(input: &Value) -> String {
+    serde_json::to_string_pretty(input).unwrap_or_default()
+}
+
+/// Text of a tool result, images replaced by a marker
+fn tool_result_text(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.clone()),
+            ContentBlock::Image { .. } => Some("[image]".to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Code fence longer than any backtick run in `content`
+fn fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    "`".repeat((longest + 1).max(3))
+}
+
+fn render_markdown(info: &SessionInfo, messages: &[TranscriptMessage]) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# {}\n\n", info.summary.unwrap_or(info.session_id)));
+    out.push_str(&format!("- Project: `{}`\n- Session: `{}`\n\n", info.project_path, info.session_id));
+
+    for message in messages {
+        out.push_str(&format!("## {}", role_title(&message.role)));
+        if !message.timestamp.is_empty() {
+            out.push_str(&format!(" · {}", message.timestamp));
+        }
+        out.push_str("\n\n");
+
+        for block in &message.blocks {
+            match block {
+                ContentBlock::Text { text } => out.push_str(&format!("{}\n\n", text)),
+                ContentBlock::Thinking { thinking } => {
+                    let quoted: Vec<String> = thinking.lines().map(|l| format!("> {}", l)).collect();
+                    out.push_str(&format!("> **Thinking**\n>\n{}\n\n", quoted.join("\n")));
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    let input = format_tool_input(input);
+                    let fence = fence(&input);
+                    out.push_str(&format!("**Tool: {}**\n\n{}json\n{}\n{}\n\n", name, fence, input, fence));
+                }
+                ContentBlock::ToolResult {
+                    tool_name,
+                    is_error,
+                    content,
+                    ..
+                } => {
+                    let text = tool_result_text(content);
+                    let fence = fence(&text);
+                    let label = if *is_error { "Tool error" } else { "Tool result" };
+                    out.push_str(&format!(
+                        "**{}{}**\n\n{}\n{}\n{}\n\n",
+                        label,
+                        tool_name.as_deref().map(|n| format!(": {}", n)).unwrap_or_default(),
+                        fence,
+                        text,
+                        fence
+                    ));
+                }
+                ContentBlock::Image { source, .. } => out.push_str(&format!("![image]({})\n\n", source)),
+            }
+        }
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Whether an image source is fine in a standalone file: inline data or a web URL,
+/// never `javascript:` or a local path
+fn is_safe_image_source(source: &str) -> bool {
+    let source = source.trim_start().as_bytes();
+    ["data:image/", "http://", "https://"].iter().any(|prefix| {
+        source
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix.as_bytes()))
+    })
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.55}
+header{border-bottom:1px solid #d0d7de;margin-bottom:1.5rem}header p{color:#656d76;margin:.25rem 0}
+.message{margin:1.25rem 0;padding:.75rem 1rem;border-radius:8px;border:1px solid #d0d7de}
+.message.user{background:#f6f8fa}.role{font-weight:600;margin-bottom:.5rem}.time{color:#656d76;font-weight:400;font-size:.85em;margin-left:.5rem}
+.text{white-space:pre-wrap;word-wrap:break-word}.thinking{color:#656d76;font-style:italic;white-space:pre-wrap}
+details{margin:.5rem 0;border:1px solid #d0d7de;border-radius:6px;padding:.25rem .75rem}details.error{border-color:#cf222e}
+summary{cursor:pointer;font-family:ui-monospace,monospace;font-size:.9em}
+pre{overflow-x:auto;background:#f6f8fa;padding:.5rem;border-radius:4px;font-size:.85em}img{max-width:100%}";
+
+fn render_html_blocks(out: &mut String, blocks: &[ContentBlock]) {
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } => {
+                out.push_str(&format!("<div class=\"text\">{}</div>\n", escape_html(text)));
+            }
+            ContentBlock::Thinking { thinking } => {
+                out.push_str(&format!(
+                    "<details><summary>Thinking</summary><div class=\"thinking\">{}</div></details>\n",
+                    escape_html(thinking)
+                ));
+            }
+            ContentBlock::ToolUse { name, input, .. } => {
+                out.push_str(&format!(
+                    "<details><summary>{}</summary><pre>{}</pre></details>\n",
+                    escape_html(name),
+                    escape_html(&format_tool_input(input))
+                ));
+            }
+            ContentBlock::ToolResult {
+                tool_name,
+                is_error,
+                content,
+                ..
+            } => {
+                let label = if *is_error { "Error" } else { "Result" };
+                out.push_str(&format!(
+                    "<details{}><summary>{}{}</summary>",
+                    if *is_error { " class=\"error\"" } else { "" },
+                    label,
+                    tool_name
+                        .as_deref()
+                        .map(|n| format!(": {}", escape_html(n)))
+                        .unwrap_or_default()
+                ));
+                for item in content {
+                    match item {
+                        ContentBlock::Text { text } => out.push_str(&format!("<pre>{}</pre>", escape_html(text))),
+                        other => render_html_blocks(out, std::slice::from_ref(other)),
+                    }
+                }
+                out.push_str("</details>\n");
+            }
+            ContentBlock::Image { source, .. } if is_safe_image_source(source) => {
+                out.push_str(&format!("<img src=\"{}\" alt=\"image\">\n", escape_html(source)));
+            }
+            ContentBlock::Image { .. } => out.push_str("<p class=\"text\">[image]</p>\n"),
+        }
+    }
+}
+
+fn render_html(info: &SessionInfo, messages: &[TranscriptMessage]) -> String {
+    let title = escape_html(info.summary.unwrap_or(info.session_id));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<header><h1>{}</h1><p>Project: <code>{}</code></p><p>Session: <code>{}</code></p></header>\n",
+        title,
+        HTML_STYLE,
+        title,
+        escape_html(info.project_path),
+        escape_html(info.session_id)
+    );
+
+    for message in messages {
+        out.push_str(&format!(
+            "<section class=\"message {}\">\n<div class=\"role\">{}<span class=\"time\">{}</span></div>\n",
+            escape_html(&message.role),
+            escape_html(role_title(&message.role)),
+            escape_html(&message.timestamp)
+        ));
+        render_html_blocks(&mut out, &message.blocks);
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
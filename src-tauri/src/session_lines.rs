@@ -0,0 +1,147 @@
+//! Line-offset index for session JSONL files
+//!
+//! Records where every line of a session file starts, so a window of lines can be read
+//! from a huge session without reading everything before it. Indexes are kept in memory
+//! and extended in place while Claude Code appends to a session.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Session files whose line offsets are kept in memory
+const MAX_CACHED_FILES: usize = 32;
+
+struct LineIndex {
+    size: u64,
+    mtime: u64,
+    head_hash: u64,
+    /// Start offset of every complete line
+    offsets: Vec<u64>,
+    /// Offset just past the last complete line
+    end: u64,
+    last_used: std::time::Instant,
+}
+
+static LINE_INDEXES: LazyLock<Mutex<HashMap<PathBuf, LineIndex>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn file_stamp(path: &Path) -> Result<(u64, u64), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Ok((metadata.len(), mtime))
+}
+
+/// Record the start of every complete line from `start` on.
+/// A trailing line without a newline is still being written and is left out.
+fn scan_lines(path: &Path, start: u64, offsets: &mut Vec<u64>) -> Result<u64, String> {
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file);
+    let mut pos = start;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).map_err(|e| e.to_string())?;
+        if read == 0 || !buf.ends_with(b"\n") {
+            break;
+        }
+        offsets.push(pos);
+        pos += read as u64;
+    }
+
+    Ok(pos)
+}
+
+/// Run `f` with an up to date line index of `path`
+fn with_line_index<T>(path: &Path, f: impl FnOnce(&LineIndex) -> Result<T, String>) -> Result<T, String> {
+    let (size, mtime) = file_stamp(path)?;
+    let mut indexes = LINE_INDEXES.lock().map_err(|e| e.to_string())?;
+
+    let fresh = indexes
+        .get(path)
+        .is_some_and(|index| index.size == size && index.mtime == mtime);
+    if !fresh {
+        let head_hash = crate::hash_first_line(path);
+        match indexes.get_mut(path) {
+            // Appended to: index only the new lines
+            Some(index) if size >= index.end && index.head_hash == head_hash => {
+                index.end = scan_lines(path, index.end, &mut index.offsets)?;
+                index.size = size;
+                index.mtime = mtime;
+            }
+            _ => {
+                if indexes.len() >= MAX_CACHED_FILES {
+                    let oldest = indexes
+                        .iter()
+                        .min_by_key(|(_, index)| index.last_used)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        indexes.remove(&oldest);
+                    }
+                }
+                let mut offsets = Vec::new();
+                let end = scan_lines(path, 0, &mut offsets)?;
+                indexes.insert(
+                    path.to_path_buf(),
+                    LineIndex {
+                        size,
+                        mtime,
+                        head_hash,
+                        offsets,
+                        end,
+                        last_used: std::time::Instant::now(),
+                    },
+                );
+            }
+        }
+    }
+
+    let index = indexes.get_mut(path).unwrap();
+    index.last_used = std::time::Instant::now();
+    f(index)
+}
+
+/// Number of complete lines in a session file
+pub fn line_count(path: &Path) -> Result<usize, String> {
+    with_line_index(path, |index| Ok(index.offsets.len()))
+}
+
+/// Read up to `count` lines starting at line `first` (1-based).
+/// Returns each line with its line number.
+pub fn read_lines(path: &Path, first: usize, count: usize) -> Result<Vec<(usize, String)>, String> {
+    let (start, last) = with_line_index(path, |index| {
+        let total = index.offsets.len();
+        if first == 0 || first > total {
+            return Ok((None, 0));
+        }
+        Ok((Some(index.offsets[first - 1]), (first + count - 1).min(total)))
+    })?;
+    let start = match start {
+        Some(start) => start,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file);
+    let mut lines = Vec::with_capacity(last + 1 - first);
+    let mut buf = Vec::new();
+
+    for line_number in first..=last {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        lines.push((line_number, String::from_utf8_lossy(&buf).trim_end().to_string()));
+    }
+
+    Ok(lines)
+}
@@ -0,0 +1,159 @@
+//! Session metadata owned by lovcode
+//!
+//! Tags, stars, notes and custom titles of sessions. They are kept in
+//! ~/.lovstudio/lovcode/session-meta.json, keyed by project and session ID, so the
+//! transcripts under ~/.claude are never written to.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionMeta {
+    pub tags: Vec<String>,
+    pub starred: bool,
+    pub note: Option<String>,
+    pub title: Option<String>, // shown instead of the summary
+}
+
+impl SessionMeta {
+    fn is_empty(&self) -> bool {
+        *self == SessionMeta::default()
+    }
+}
+
+/// Changes to a session's metadata. Omitted fields are kept, an empty note or
+/// title clears it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SessionMetaUpdate {
+    pub tags: Option<Vec<String>>,
+    pub starred: Option<bool>,
+    pub note: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// project_id -> session_id -> metadata
+type MetaStore = HashMap<String, HashMap<String, SessionMeta>>;
+
+static STORE: Mutex<Option<MetaStore>> = Mutex::new(None);
+
+fn get_store_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("session-meta.json")
+}
+
+fn with_store<T>(f: impl FnOnce(&mut MetaStore) -> T) -> Result<T, String> {
+    let mut guard = STORE.lock().map_err(|e| e.to_string())?;
+    let store = guard.get_or_insert_with(|| {
+        fs::read_to_string(get_store_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    });
+    Ok(f(store))
+}
+
+fn save_store(store: &MetaStore) -> Result<(), String> {
+    let path = get_store_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
+    fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, &path).map_err(|e| e.to_string())
+}
+
+/// Tags are compared case-insensitively and stored lowercase
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn get(project_id: &str, session_id: &str) -> SessionMeta {
+    with_store(|store| {
+        store
+            .get(project_id)
+            .and_then(|sessions| sessions.get(session_id))
+            .cloned()
+            .unwrap_or_default()
+    })
+    .unwrap_or_default()
+}
+
+pub fn update(project_id: &str, session_id: &str, update: SessionMetaUpdate) -> Result<SessionMeta, String> {
+    with_store(|store| {
+        let sessions = store.entry(project_id.to_string()).or_default();
+        let mut meta = sessions.remove(session_id).unwrap_or_default();
+        if let Some(tags) = update.tags {
+            meta.tags = normalize_tags(tags);
+        }
+        if let Some(starred) = update.starred {
+            meta.starred = starred;
+        }
+        if let Some(note) = update.note {
+            meta.note = non_empty(note);
+        }
+        if let Some(title) = update.title {
+            meta.title = non_empty(title);
+        }
+
+        if !meta.is_empty() {
+            sessions.insert(session_id.to_string(), meta.clone());
+        }
+        if sessions.is_empty() {
+            store.remove(project_id);
+        }
+        save_store(store)?;
+        Ok(meta)
+    })?
+}
+
+/// Fill in the metadata of listed sessions
+pub fn apply(sessions: &mut [crate::Session]) {
+    let _ = with_store(|store| {
+        for session in sessions.iter_mut() {
+            if let Some(meta) = store.get(&session.project_id).and_then(|s| s.get(&session.id)) {
+                session.meta = meta.clone();
+            }
+        }
+    });
+}
+
+/// Tags in use, optionally within one project, most used first
+pub fn tag_counts(project_id: Option<&str>) -> Vec<TagCount> {
+    let counts = with_store(|store| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (_, sessions) in store.iter().filter(|(id, _)| project_id.is_none_or(|p| p == id.as_str())) {
+            for tag in sessions.values().flat_map(|meta| &meta.tags) {
+                *counts.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    })
+    .unwrap_or_default();
+
+    let mut counts: Vec<TagCount> = counts.into_iter().map(|(tag, count)| TagCount { tag, count }).collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    counts
+}
@@ -0,0 +1,351 @@
+//! Sessions of other coding agents
+//!
+//! Each agent's transcript format is read by a `SessionSource` adapter and normalized
+//! into the same `Session`/`Message` types used for Claude Code sessions. Sessions are
+//! grouped under the project ID Claude Code would use for their working directory, so
+//! they show up next to the Claude sessions of the same project.
+//!
+//! Supported: Codex rollouts (~/.codex/sessions) and Gemini CLI chats (~/.gemini/tmp).
+
+use crate::{Message, Session, SessionUsage};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A session read in full from another agent's transcript
+#[derive(Clone)]
+pub struct ImportedSession {
+    pub session: Session,
+    pub messages: Vec<Message>,
+}
+
+pub trait SessionSource: Send + Sync {
+    /// Stable name, stored as `Session::source`
+    fn name(&self) -> &'static str;
+
+    /// Every transcript file of this agent
+    fn session_files(&self) -> Vec<PathBuf>;
+
+    /// Parse a transcript file into the shared model
+    fn read_session(&self, path: &Path) -> Result<ImportedSession, String>;
+}
+
+pub fn all_sources() -> Vec<Box<dyn SessionSource>> {
+    vec![Box::new(CodexSource), Box::new(GeminiSource)]
+}
+
+pub fn find_source(name: &str) -> Option<Box<dyn SessionSource>> {
+    all_sources().into_iter().find(|source| source.name() == name)
+}
+
+/// Transcript file of a session, matched by file stem
+pub fn find_session_file(source: &dyn SessionSource, session_id: &str) -> Option<PathBuf> {
+    source
+        .session_files()
+        .into_iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy() == session_id))
+}
+
+/// Last session read by `read_session_by_id`, so paging through a session parses its
+/// transcript once
+struct LastRead {
+    path: PathBuf,
+    stamp: (u64, u64), // size, mtime of the transcript
+    session: Arc<ImportedSession>,
+}
+
+static LAST_READ: Mutex<Option<LastRead>> = Mutex::new(None);
+
+/// Read a session in full. The transcript is found through the session metadata cache,
+/// and only looked for among all of the source's files when the cache does not know it.
+pub fn read_session_by_id(source: &dyn SessionSource, session_id: &str) -> Result<ImportedSession, String> {
+    let path = crate::session_cache::imported_session_path(source.name(), session_id)
+        .filter(|path| path.exists())
+        .or_else(|| find_session_file(source, session_id))
+        .ok_or("Session not found")?;
+    let stamp = crate::file_size_and_mtime(&path)?;
+
+    let lock = || match LAST_READ.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let cached = lock()
+        .as_ref()
+        .filter(|last| last.path == path && last.stamp == stamp)
+        .map(|last| last.session.clone());
+    if let Some(session) = cached {
+        return Ok((*session).clone());
+    }
+
+    let session = source.read_session(&path)?;
+    *lock() = Some(LastRead {
+        path,
+        stamp,
+        session: Arc::new(session.clone()),
+    });
+    Ok(session)
+}
+
+/// Sessions of every source, without their messages. Only transcripts that changed
+/// since the last listing are read, the rest come from the session metadata cache.
+pub fn list_imported_sessions() -> Vec<Session> {
+    all_sources()
+        .iter()
+        .flat_map(|source| {
+            crate::session_cache::lookup_imported(&source.session_files(), |path| {
+                source.read_session(path).ok().map(|imported| imported.session)
+            })
+        })
+        .collect()
+}
+
+fn collect_files(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, extension, files);
+        } else if path.extension().is_some_and(|e| e == extension) {
+            files.push(path);
+        }
+    }
+}
+
+fn file_modified_secs(path: &Path) -> u64 {
+    fs::metadata(path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Title from the first user message, as Claude sessions without a summary get
+fn first_user_summary(messages: &[Message]) -> Option<String> {
+    messages
+        .iter()
+        .find(|m| m.role == "user" && !m.is_tool)
+        .map(|m| m.content.lines().next().unwrap_or_default().chars().take(100).collect())
+}
+
+/// Normalize parsed messages into an imported session. Message uuids are made
+/// unique across sessions, as the search index expects.
+fn finish_session(
+    source: &str,
+    path: &Path,
+    cwd: Option<String>,
+    fallback_project_id: String,
+    mut messages: Vec<Message>,
+    usage: Option<SessionUsage>,
+) -> ImportedSession {
+    let id = path.file_stem().unwrap().to_string_lossy().to_string();
+    for (idx, message) in messages.iter_mut().enumerate() {
+        message.uuid = format!("{}:{}", id, idx);
+    }
+
+    let session = Session {
+        project_id: cwd
+            .as_deref()
+            .map(crate::encode_cwd_project_id)
+            .unwrap_or(fallback_project_id),
+        project_path: cwd,
+        summary: first_user_summary(&messages),
+        message_count: messages.len(),
+        last_modified: file_modified_secs(path),
+        usage,
+        source: source.to_string(),
+        meta: Default::default(),
+        id,
+    };
+    ImportedSession { session, messages }
+}
+
+/// Text of message content given as a string or as parts with `text`
+fn parts_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn push_message(messages: &mut Vec<Message>, role: &str, content: String, timestamp: &str, is_tool: bool, line_number: usize) {
+    if content.trim().is_empty() {
+        return;
+    }
+    messages.push(Message {
+        uuid: String::new(),
+        role: role.to_string(),
+        content,
+        timestamp: timestamp.to_string(),
+        is_meta: false,
+        is_tool,
+        line_number,
+    });
+}
+
+// ============================================================================
+// Codex
+// ============================================================================
+
+/// Codex CLI rollouts: ~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl
+pub struct CodexSource;
+
+impl SessionSource for CodexSource {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn session_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        collect_files(&crate::get_codex_dir().join("sessions"), "jsonl", &mut files);
+        files
+    }
+
+    fn read_session(&self, path: &Path) -> Result<ImportedSession, String> {
+        let mut messages = Vec::new();
+        let mut cwd = None;
+        let mut usage = None;
+        let mut line_number = 0;
+
+        crate::for_each_complete_line(path, 0, |line| {
+            line_number += 1;
+            let value: Value = match serde_json::from_str(line) {
+                Ok(value) => value,
+                Err(_) => return true,
+            };
+            let timestamp = value.get("timestamp").and_then(|t| t.as_str()).unwrap_or_default();
+            // Newer rollouts wrap every item as { type, payload }, older ones write items directly
+            let (kind, item) = match value.get("payload") {
+                Some(payload) => (value.get("type").and_then(|t| t.as_str()).unwrap_or_default(), payload),
+                None => ("response_item", &value),
+            };
+
+            match (kind, item.get("type").and_then(|t| t.as_str())) {
+                ("session_meta", _) => {
+                    cwd = item.get("cwd").and_then(|c| c.as_str()).map(String::from);
+                }
+                ("turn_context", _) if cwd.is_none() => {
+                    cwd = item.get("cwd").and_then(|c| c.as_str()).map(String::from);
+                }
+                ("response_item", Some("message")) => {
+                    let role = item.get("role").and_then(|r| r.as_str()).unwrap_or_default();
+                    let text = parts_text(item.get("content"));
+                    // Skip injected instructions and environment context
+                    if (role == "user" || role == "assistant")
+                        && !text.starts_with("<environment_context>")
+                        && !text.starts_with("<user_instructions>")
+                    {
+                        push_message(&mut messages, role, text, timestamp, false, line_number);
+                    }
+                }
+                ("response_item", Some("function_call" | "custom_tool_call" | "local_shell_call")) => {
+                    let name = item.get("name").and_then(|n| n.as_str()).unwrap_or("shell");
+                    let arguments = item
+                        .get("arguments")
+                        .or_else(|| item.get("input"))
+                        .or_else(|| item.get("action"))
+                        .map(|a| a.as_str().map(String::from).unwrap_or_else(|| a.to_string()))
+                        .unwrap_or_default();
+                    push_message(&mut messages, "assistant", format!("{}: {}", name, arguments), timestamp, true, line_number);
+                }
+                ("response_item", Some("function_call_output" | "custom_tool_call_output")) => {
+                    let output = match item.get("output") {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(other) => other.to_string(),
+                        None => String::new(),
+                    };
+                    push_message(&mut messages, "user", output, timestamp, true, line_number);
+                }
+                ("event_msg", Some("token_count")) => {
+                    // Running totals, the last one covers the whole session
+                    if let Some(total) = item.pointer("/info/total_token_usage") {
+                        let get = |key: &str| total.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                        let cached = get("cached_input_tokens");
+                        usage = Some(SessionUsage {
+                            input_tokens: get("input_tokens").saturating_sub(cached),
+                            output_tokens: get("output_tokens"),
+                            cache_creation_tokens: 0,
+                            cache_read_tokens: cached,
+                            // Pricing is only known for Claude models
+                            cost_usd: 0.0,
+                            by_model: Default::default(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+            true
+        })?;
+
+        Ok(finish_session(self.name(), path, cwd, "codex".to_string(), messages, usage))
+    }
+}
+
+// ============================================================================
+// Gemini CLI
+// ============================================================================
+
+/// Gemini CLI chats: ~/.gemini/tmp/<project hash>/chats/session-*.json
+pub struct GeminiSource;
+
+impl SessionSource for GeminiSource {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn session_files(&self) -> Vec<PathBuf> {
+        let tmp_dir = dirs::home_dir().unwrap_or_default().join(".gemini").join("tmp");
+        fs::read_dir(tmp_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|entry| fs::read_dir(entry.path().join("chats")).into_iter().flatten().flatten())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect()
+    }
+
+    fn read_session(&self, path: &Path) -> Result<ImportedSession, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let chat: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let mut messages = Vec::new();
+        let mut usage = SessionUsage::default();
+
+        for (idx, item) in chat.get("messages").and_then(|m| m.as_array()).into_iter().flatten().enumerate() {
+            let timestamp = item.get("timestamp").and_then(|t| t.as_str()).unwrap_or_default();
+            let role = match item.get("type").and_then(|t| t.as_str()) {
+                Some("user") => "user",
+                Some("gemini") => "assistant",
+                _ => continue,
+            };
+            push_message(&mut messages, role, parts_text(item.get("content")), timestamp, false, idx + 1);
+
+            for call in item.get("toolCalls").and_then(|c| c.as_array()).into_iter().flatten() {
+                let name = call.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                let args = call.get("args").map(|a| a.to_string()).unwrap_or_default();
+                push_message(&mut messages, "assistant", format!("{}: {}", name, args), timestamp, true, idx + 1);
+            }
+
+            if let Some(tokens) = item.get("tokens") {
+                let get = |key: &str| tokens.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                let cached = get("cached");
+                usage.input_tokens += get("input").saturating_sub(cached);
+                usage.output_tokens += get("output") + get("thoughts");
+                usage.cache_read_tokens += cached;
+            }
+        }
+
+        // Only a hash of the project path is recorded
+        let project_hash = chat
+            .get("projectHash")
+            .and_then(|h| h.as_str())
+            .map(|h| format!("gemini-{}", h))
+            .unwrap_or_else(|| "gemini".to_string());
+        Ok(finish_session(self.name(), path, None, project_hash, messages, Some(usage)))
+    }
+}
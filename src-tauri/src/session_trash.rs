@@ -0,0 +1,203 @@
+//! Session archive and trash
+//!
+//! Archiving or trashing a session moves its transcript, along with the subagent
+//! transcripts it spawned, out of ~/.claude/projects into ~/.lovstudio/lovcode/trash.
+//! Every removed session gets its own directory holding the moved files and an
+//! `entry.json` recording where they came from, so they can be put back. Trashed
+//! sessions are purged once older than the configured retention, archived ones are kept.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ENTRY_FILE: &str = "entry.json";
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// How often a running app purges expired trash
+pub const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalKind {
+    Archived,
+    Trashed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedFile {
+    pub original_path: String,
+    /// Name inside the entry directory
+    pub stored_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: RemovalKind,
+    pub project_id: String,
+    pub session_id: String,
+    pub summary: Option<String>,
+    pub removed_at: u64, // unix seconds
+    pub files: Vec<TrashedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrashSettings {
+    pub retention_days: u32, // 0 keeps trashed sessions until restored
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+        }
+    }
+}
+
+fn get_trash_dir() -> PathBuf {
+    crate::get_lovstudio_dir().join("trash")
+}
+
+/// Directory of an entry. IDs come from the frontend, so only a single path component
+/// resolving to a direct child of the trash directory is accepted.
+fn get_entry_dir(id: &str) -> Result<PathBuf, String> {
+    let invalid = || format!("Invalid trash entry ID: {}", id);
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(invalid());
+    }
+    let entry_dir = get_trash_dir()
+        .join(id)
+        .canonicalize()
+        .map_err(|_| format!("Trash entry not found: {}", id))?;
+    let trash_dir = get_trash_dir().canonicalize().map_err(|e| e.to_string())?;
+    if entry_dir.parent() != Some(trash_dir.as_path()) {
+        return Err(invalid());
+    }
+    Ok(entry_dir)
+}
+
+fn get_settings_path() -> PathBuf {
+    crate::get_lovstudio_dir().join("trash-settings.json")
+}
+
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+pub fn load_settings() -> TrashSettings {
+    fs::read_to_string(get_settings_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &TrashSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+fn write_entry(entry_dir: &Path, entry: &TrashEntry) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(entry).map_err(|e| e.to_string())?;
+    fs::write(entry_dir.join(ENTRY_FILE), content).map_err(|e| e.to_string())
+}
+
+fn read_entry(entry_dir: &Path) -> Option<TrashEntry> {
+    let content = fs::read_to_string(entry_dir.join(ENTRY_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Move `files` (session files or directories) into a new trash entry
+pub fn remove_session(
+    project_id: &str,
+    session_id: &str,
+    summary: Option<String>,
+    files: &[PathBuf],
+    kind: RemovalKind,
+) -> Result<TrashEntry, String> {
+    let removed_at = now_secs();
+    let id = format!("{}-{}", chrono::Utc::now().timestamp_millis(), session_id);
+    let entry_dir = get_trash_dir().join(&id);
+    fs::create_dir_all(&entry_dir).map_err(|e| e.to_string())?;
+
+    let mut entry = TrashEntry {
+        id,
+        kind,
+        project_id: project_id.to_string(),
+        session_id: session_id.to_string(),
+        summary,
+        removed_at,
+        files: Vec::new(),
+    };
+
+    for (idx, path) in files.iter().enumerate() {
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let stored_name = format!("{}-{}", idx, file_name);
+        if let Err(e) = fs::rename(path, entry_dir.join(&stored_name)) {
+            // Put back what was already moved so the session is not left half removed
+            write_entry(&entry_dir, &entry)?;
+            let _ = restore(&entry.id);
+            return Err(format!("Failed to move {}: {}", path.display(), e));
+        }
+        entry.files.push(TrashedFile {
+            original_path: path.to_string_lossy().to_string(),
+            stored_name,
+        });
+    }
+
+    write_entry(&entry_dir, &entry)?;
+    Ok(entry)
+}
+
+/// Move the files of an entry back to where they came from and drop the entry
+pub fn restore(id: &str) -> Result<TrashEntry, String> {
+    let entry_dir = get_entry_dir(id)?;
+    let entry = read_entry(&entry_dir).ok_or_else(|| format!("Trash entry not found: {}", id))?;
+
+    if let Some(existing) = entry.files.iter().find(|f| Path::new(&f.original_path).exists()) {
+        return Err(format!("{} already exists", existing.original_path));
+    }
+    for file in &entry.files {
+        let original = Path::new(&file.original_path);
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::rename(entry_dir.join(&file.stored_name), original).map_err(|e| e.to_string())?;
+    }
+
+    fs::remove_dir_all(&entry_dir).map_err(|e| e.to_string())?;
+    Ok(entry)
+}
+
+/// Archived and trashed sessions, most recently removed first
+pub fn list() -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = fs::read_dir(get_trash_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| read_entry(&entry.path()))
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.removed_at));
+    entries
+}
+
+/// Delete trashed sessions older than the retention period. Returns how many were purged.
+pub fn purge_expired() -> Result<usize, String> {
+    let retention_days = load_settings().retention_days;
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = now_secs().saturating_sub(retention_days as u64 * 86_400);
+    let mut purged = 0;
+    for entry in list() {
+        if entry.kind == RemovalKind::Trashed && entry.removed_at <= cutoff {
+            fs::remove_dir_all(get_entry_dir(&entry.id)?).map_err(|e| e.to_string())?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
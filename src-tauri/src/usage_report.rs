@@ -0,0 +1,483 @@
+//! Usage reports
+//!
+//! Tokens and cost of Claude Code sessions over a date range, grouped by day, week,
+//! month, project, model or session. The session metadata cache keeps the usage and time of
+//! every assistant message, deduplicated across sessions, so a report only reads the
+//! files that changed since the last one. Messages count toward the local day they
+//! were sent on; subagent usage counts toward the session that spawned it.
+//!
+//! The same aggregation is exported as CSV or JSON rows for spreadsheets, one per model
+//! within each group. Grouped by a period, rows are also split by project, so one
+//! export by month gives spend per month, project and model. Row columns are a stable
+//! format: add new ones at the end and never rename existing ones.
+
+use crate::session_cache::{self, CachedMessage, CachedSessionFile};
+use crate::{ModelUsage, SessionUsage};
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Local dates as YYYY-MM-DD, both inclusive. Open ends are unbounded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroupBy {
+    Day,
+    Week,
+    Month,
+    Project,
+    Model,
+    Session,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageGroup {
+    /// Date, ISO week (2025-W07), month (2025-02), project ID, model ID or session ID
+    pub key: String,
+    /// Project path or session summary
+    pub label: Option<String>,
+    pub usage: SessionUsage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsagePoint {
+    pub date: String,
+    pub usage: SessionUsage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub group_by: UsageGroupBy,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub groups: Vec<UsageGroup>,
+    /// Every day from the first to the last of the range, including days without usage
+    pub daily: Vec<UsagePoint>,
+    pub totals: SessionUsage,
+}
+
+/// Exported usage of one model within a group. Columns the grouping does not break
+/// down by are left empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageRow {
+    pub date: String, // YYYY-MM-DD by day, ISO week by week, YYYY-MM by month
+    pub project_path: String,
+    pub session_id: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// CSV header, in `UsageRow` field order
+const CSV_COLUMNS: [&str; 9] = [
+    "date",
+    "project_path",
+    "session_id",
+    "model",
+    "input_tokens",
+    "output_tokens",
+    "cache_creation_tokens",
+    "cache_read_tokens",
+    "cost_usd",
+];
+
+/// A transcript and the session its usage counts toward
+struct UsageFile {
+    project_id: String,
+    session_id: String,
+    path: PathBuf,
+    subagent: bool,
+}
+
+/// Period a date falls in, for groupings by time
+fn period_key(group_by: UsageGroupBy, date: NaiveDate) -> Option<String> {
+    let format = match group_by {
+        UsageGroupBy::Day => DATE_FORMAT,
+        UsageGroupBy::Week => "%G-W%V",
+        UsageGroupBy::Month => "%Y-%m",
+        _ => return None,
+    };
+    Some(date.format(format).to_string())
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), DATE_FORMAT).map_err(|e| format!("Invalid date {}: {}", date, e))
+}
+
+/// Unix time of local midnight starting `date`
+fn day_start(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+}
+
+fn local_date(timestamp: i64) -> NaiveDate {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.date_naive())
+        .unwrap_or_default()
+}
+
+/// Session transcripts and subagent transcripts of every project
+fn list_usage_files() -> Vec<UsageFile> {
+    let projects_dir = crate::get_claude_dir().join("projects");
+    let mut files = Vec::new();
+    for project_entry in fs::read_dir(&projects_dir).into_iter().flatten().flatten() {
+        let project_dir = project_entry.path();
+        if !project_dir.is_dir() {
+            continue;
+        }
+        let project_id = project_entry.file_name().to_string_lossy().to_string();
+
+        for entry in fs::read_dir(&project_dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".jsonl") && !name.starts_with("agent-") {
+                let session_id = name.trim_end_matches(".jsonl").to_string();
+                files.push(UsageFile {
+                    project_id: project_id.clone(),
+                    session_id,
+                    path,
+                    subagent: false,
+                });
+            }
+        }
+        for path in crate::list_subagent_files(&project_dir) {
+            if let Some(session_id) = crate::read_subagent_parent(&path) {
+                files.push(UsageFile {
+                    project_id: project_id.clone(),
+                    session_id,
+                    path,
+                    subagent: true,
+                });
+            }
+        }
+    }
+    files
+}
+
+/// First and last day of a range, and the unix seconds it covers
+struct DateRange {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    seconds: Range<i64>,
+}
+
+fn parse_range(range: &UsageRange) -> Result<DateRange, String> {
+    let from = range.from.as_deref().map(parse_date).transpose()?;
+    let to = range.to.as_deref().map(parse_date).transpose()?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(format!("Range starts after it ends: {} > {}", from, to));
+        }
+    }
+    let start = from.map(day_start).unwrap_or(i64::MIN);
+    let end = to.and_then(|to| to.succ_opt()).map(day_start).unwrap_or(i64::MAX);
+    Ok(DateRange {
+        from,
+        to,
+        seconds: start..end,
+    })
+}
+
+/// Usage files with their cached metadata, messages deduplicated across all of them
+fn lookup_usage_files() -> Vec<(UsageFile, CachedSessionFile)> {
+    let files = list_usage_files();
+    let paths: Vec<PathBuf> = files.iter().map(|file| file.path.clone()).collect();
+    let cached = session_cache::lookup_files(&paths);
+    files.into_iter().zip(cached).collect()
+}
+
+/// Local date of a message within the range, None for messages outside of it
+fn message_date(range: &DateRange, message: &CachedMessage) -> Option<NaiveDate> {
+    message
+        .timestamp
+        .filter(|timestamp| range.seconds.contains(timestamp))
+        .map(local_date)
+}
+
+pub fn build_report(range: &UsageRange, group_by: UsageGroupBy) -> Result<UsageReport, String> {
+    let date_range = parse_range(range)?;
+    let (from, to) = (date_range.from, date_range.to);
+
+    let mut groups: HashMap<String, SessionUsage> = HashMap::new();
+    let mut daily: BTreeMap<NaiveDate, SessionUsage> = BTreeMap::new();
+    let mut totals = SessionUsage::default();
+    let mut summaries: HashMap<String, String> = HashMap::new();
+
+    for (file, entry) in lookup_usage_files() {
+        if let (false, Some(summary)) = (file.subagent, &entry.summary) {
+            summaries.insert(file.session_id.clone(), summary.clone());
+        }
+        for message in &entry.messages {
+            let date = match message_date(&date_range, message) {
+                Some(date) => date,
+                None => continue,
+            };
+            let key = match group_by {
+                UsageGroupBy::Project => file.project_id.clone(),
+                UsageGroupBy::Model => message.model.clone(),
+                UsageGroupBy::Session => file.session_id.clone(),
+                _ => period_key(group_by, date).unwrap_or_default(),
+            };
+            crate::add_model_usage(groups.entry(key).or_default(), &message.model, &message.usage);
+            crate::add_model_usage(daily.entry(date).or_default(), &message.model, &message.usage);
+            crate::add_model_usage(&mut totals, &message.model, &message.usage);
+        }
+    }
+
+    let mut groups: Vec<UsageGroup> = groups
+        .into_iter()
+        .map(|(key, mut usage)| {
+            crate::pricing::apply_costs(&mut usage);
+            let label = match group_by {
+                UsageGroupBy::Project => Some(crate::decode_project_path(&key)),
+                UsageGroupBy::Session => summaries.get(&key).cloned(),
+                _ => None,
+            };
+            UsageGroup { key, label, usage }
+        })
+        .collect();
+    match group_by {
+        UsageGroupBy::Day | UsageGroupBy::Week | UsageGroupBy::Month => groups.sort_by(|a, b| a.key.cmp(&b.key)),
+        _ => groups.sort_by(|a, b| b.usage.cost_usd.total_cmp(&a.usage.cost_usd).then_with(|| a.key.cmp(&b.key))),
+    }
+
+    // Fill in days without usage so charts get an evenly spaced series
+    let first_day = from.or_else(|| daily.keys().next().copied());
+    let last_day = to.or_else(|| daily.keys().next_back().copied());
+    let mut series = Vec::new();
+    if let (Some(first_day), Some(last_day)) = (first_day, last_day) {
+        for date in first_day.iter_days().take_while(|date| *date <= last_day) {
+            let mut usage = daily.remove(&date).unwrap_or_default();
+            crate::pricing::apply_costs(&mut usage);
+            series.push(UsagePoint {
+                date: date.format(DATE_FORMAT).to_string(),
+                usage,
+            });
+        }
+    }
+    crate::pricing::apply_costs(&mut totals);
+
+    Ok(UsageReport {
+        group_by,
+        from: range.from.clone(),
+        to: range.to.clone(),
+        groups,
+        daily: series,
+        totals,
+    })
+}
+
+/// Cost per project and local day within a range, for checking budgets of several
+/// periods against one pass over the cache
+pub fn daily_project_costs(range: &UsageRange) -> Result<HashMap<String, BTreeMap<NaiveDate, f64>>, String> {
+    let date_range = parse_range(range)?;
+    // (project_id, date, model) -> usage
+    let mut usage: HashMap<(String, NaiveDate, String), ModelUsage> = HashMap::new();
+
+    for (file, entry) in lookup_usage_files() {
+        for message in &entry.messages {
+            if let Some(date) = message_date(&date_range, message) {
+                usage
+                    .entry((file.project_id.clone(), date, message.model.clone()))
+                    .or_default()
+                    .add(&message.usage);
+            }
+        }
+    }
+
+    let pricing = crate::pricing::table();
+    let mut costs: HashMap<String, BTreeMap<NaiveDate, f64>> = HashMap::new();
+    for ((project_id, date, model), usage) in usage {
+        *costs.entry(project_id).or_default().entry(date).or_default() += pricing.price_for(&model).cost(&usage);
+    }
+    Ok(costs)
+}
+
+/// Usage rows per group and model, ordered by date, project, session and model
+pub fn export_rows(range: &UsageRange, group_by: UsageGroupBy) -> Result<Vec<UsageRow>, String> {
+    let date_range = parse_range(range)?;
+    // (date, project_id, session_id, model) -> usage
+    let mut rows: BTreeMap<(String, String, String, String), ModelUsage> = BTreeMap::new();
+
+    for (file, entry) in lookup_usage_files() {
+        for message in &entry.messages {
+            let date = match message_date(&date_range, message) {
+                Some(date) => date,
+                None => continue,
+            };
+            let (date, project_id, session_id) = match group_by {
+                UsageGroupBy::Project => (String::new(), file.project_id.clone(), String::new()),
+                UsageGroupBy::Model => (String::new(), String::new(), String::new()),
+                UsageGroupBy::Session => (String::new(), file.project_id.clone(), file.session_id.clone()),
+                // Periods are split by project as well
+                _ => (period_key(group_by, date).unwrap_or_default(), file.project_id.clone(), String::new()),
+            };
+            rows.entry((date, project_id, session_id, message.model.clone()))
+                .or_default()
+                .add(&message.usage);
+        }
+    }
+
+    let pricing = crate::pricing::table();
+    let mut project_paths: HashMap<String, String> = HashMap::new();
+    Ok(rows
+        .into_iter()
+        .map(|((date, project_id, session_id, model), usage)| {
+            let project_path = if project_id.is_empty() {
+                String::new()
+            } else {
+                project_paths
+                    .entry(project_id)
+                    .or_insert_with_key(|id| crate::decode_project_path(id))
+                    .clone()
+            };
+            UsageRow {
+                date,
+                project_path,
+                session_id,
+                cost_usd: pricing.price_for(&model).cost(&usage),
+                model,
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                cache_creation_tokens: usage.cache_creation_5m_tokens + usage.cache_creation_1h_tokens,
+                cache_read_tokens: usage.cache_read_tokens,
+            }
+        })
+        .collect())
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render_rows(rows: &[UsageRow], format: UsageExportFormat) -> Result<String, String> {
+    match format {
+        UsageExportFormat::Json => serde_json::to_string_pretty(rows).map_err(|e| e.to_string()),
+        UsageExportFormat::Csv => {
+            let mut out = CSV_COLUMNS.join(",");
+            out.push('\n');
+            for row in rows {
+                let fields = [
+                    csv_field(&row.date),
+                    csv_field(&row.project_path),
+                    csv_field(&row.session_id),
+                    csv_field(&row.model),
+                    row.input_tokens.to_string(),
+                    row.output_tokens.to_string(),
+                    row.cache_creation_tokens.to_string(),
+                    row.cache_read_tokens.to_string(),
+                    format!("{:.6}", row.cost_usd),
+                ];
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> UsageRow {
+        UsageRow {
+            date: "2025-06".to_string(),
+            project_path: "/home/al/work, \"lovcode\"".to_string(),
+            session_id: String::new(),
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            input_tokens: 1200,
+            output_tokens: 340,
+            cache_creation_tokens: 5000,
+            cache_read_tokens: 80000,
+            cost_usd: 0.0561,
+        }
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("claude-opus-4-5"), "claude-opus-4-5");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn csv_has_stable_columns() {
+        let csv = render_rows(&[row()], UsageExportFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("date,project_path,session_id,model,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,cost_usd")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("2025-06,\"/home/al/work, \"\"lovcode\"\"\",,claude-sonnet-4-5-20250929,1200,340,5000,80000,0.056100")
+        );
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn csv_of_no_rows_is_the_header() {
+        let csv = render_rows(&[], UsageExportFormat::Csv).unwrap();
+        assert_eq!(csv, format!("{}\n", CSV_COLUMNS.join(",")));
+    }
+
+    #[test]
+    fn json_fields_follow_csv_columns() {
+        let json = render_rows(&[row()], UsageExportFormat::Json).unwrap();
+        let positions: Vec<usize> = CSV_COLUMNS
+            .iter()
+            .map(|column| json.find(&format!("\"{}\":", column)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0].as_object().unwrap().len(), CSV_COLUMNS.len());
+        assert_eq!(value[0]["input_tokens"], 1200);
+        assert_eq!(value[0]["session_id"], "");
+    }
+
+    #[test]
+    fn periods_use_their_key_format() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        assert_eq!(period_key(UsageGroupBy::Day, date).as_deref(), Some("2025-01-01"));
+        assert_eq!(period_key(UsageGroupBy::Week, date).as_deref(), Some("2025-W01"));
+        assert_eq!(period_key(UsageGroupBy::Month, date).as_deref(), Some("2025-01"));
+        assert_eq!(period_key(UsageGroupBy::Project, date), None);
+    }
+}
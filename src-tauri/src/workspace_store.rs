@@ -174,6 +174,14 @@ pub fn add_project(path: String) -> Result<WorkspaceProject, String> {
         return Err(format!("Project '{}' already exists", path));
     }
 
+    let project = push_project(&mut data, path);
+    save_workspace(&data)?;
+
+    Ok(project)
+}
+
+/// Append a project to loaded workspace data, without saving
+fn push_project(data: &mut WorkspaceData, path: String) -> WorkspaceProject {
     // Extract project name from path
     let name = std::path::Path::new(&path)
         .file_name()
@@ -203,9 +211,7 @@ pub fn add_project(path: String) -> Result<WorkspaceProject, String> {
         data.active_project_id = Some(project.id.clone());
     }
 
-    save_workspace(&data)?;
-
-    Ok(project)
+    project
 }
 
 /// Remove a project from the workspace
@@ -247,7 +253,19 @@ pub fn set_active_project(id: &str) -> Result<(), String> {
 /// Create a new feature in a project
 pub fn create_feature(project_id: &str, name: String, description: Option<String>) -> Result<Feature, String> {
     let mut data = load_workspace()?;
+    let feature = push_feature(&mut data, project_id, name, description)?;
+    save_workspace(&data)?;
+
+    Ok(feature)
+}
 
+/// Append a feature to a project of loaded workspace data, without saving
+fn push_feature(
+    data: &mut WorkspaceData,
+    project_id: &str,
+    name: String,
+    description: Option<String>,
+) -> Result<Feature, String> {
     // Increment global feature counter
     let seq = data.feature_counter.unwrap_or(0) + 1;
     data.feature_counter = Some(seq);
@@ -285,8 +303,6 @@ pub fn create_feature(project_id: &str, name: String, description: Option<String
         project.active_feature_id = Some(feature.id.clone());
     }
 
-    save_workspace(&data)?;
-
     Ok(feature)
 }
 
@@ -480,3 +496,59 @@ pub fn get_pending_reviews() -> Result<Vec<(String, String, String)>, String> {
 
     Ok(reviews)
 }
+
+/// Find the project and feature a chat session belongs to, creating them as needed.
+///
+/// The project is matched by path. Within it, the feature already linked to the session
+/// (`chat_session_id`) wins, then the requested feature, then a new feature named
+/// `title` and linked to the session. Everything is changed in one load and save.
+pub fn find_or_create_session_feature(
+    project_path: &str,
+    chat_session_id: &str,
+    feature_id: Option<&str>,
+    title: String,
+) -> Result<(String, String), String> {
+    let mut data = load_workspace()?;
+    let mut changed = false;
+    let project_id = match data.projects.iter().find(|p| p.path == project_path) {
+        Some(project) => project.id.clone(),
+        None => {
+            changed = true;
+            push_project(&mut data, project_path.to_string()).id
+        }
+    };
+
+    let project = data
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| format!("Project '{}' not found", project_path))?;
+    let existing = project
+        .features
+        .iter()
+        .find(|f| f.chat_session_id.as_deref() == Some(chat_session_id))
+        .or_else(|| feature_id.and_then(|id| project.features.iter().find(|f| f.id == id)))
+        .map(|f| f.id.clone());
+
+    let feature_id = match existing {
+        Some(feature_id) => feature_id,
+        None => {
+            let feature = push_feature(&mut data, &project_id, title, None)?;
+            if let Some(created) = data
+                .projects
+                .iter_mut()
+                .find(|p| p.id == project_id)
+                .and_then(|p| p.features.iter_mut().find(|f| f.id == feature.id))
+            {
+                created.chat_session_id = Some(chat_session_id.to_string());
+            }
+            changed = true;
+            feature.id
+        }
+    };
+
+    if changed {
+        save_workspace(&data)?;
+    }
+    Ok((project_id, feature_id))
+}
@@ -48,6 +48,112 @@ export interface SessionUsage {
   cache_creation_tokens: number;
   cache_read_tokens: number;
   cost_usd: number;
+  by_model: Record<string, ModelUsage>; // keyed by message.model
+}
+
+export interface ModelUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_5m_tokens: number;
+  cache_creation_1h_tokens: number;
+  cache_read_tokens: number;
+  cost_usd: number;
+}
+
+// USD per 1M tokens
+export interface ModelPrice {
+  input: number;
+  output: number;
+  cache_write_5m: number;
+  cache_write_1h: number;
+  cache_read: number;
+}
+
+export interface PricingTable {
+  version: number;
+  updated: string;
+  fallback: ModelPrice;
+  models: Record<string, ModelPrice>; // model ID prefix -> price
+  overridden: boolean;
+}
+
+// Local dates as YYYY-MM-DD, both inclusive
+export interface UsageRange {
+  from?: string | null;
+  to?: string | null;
+}
+
+export type UsageGroupBy = "day" | "week" | "month" | "project" | "model" | "session";
+
+export interface UsageGroup {
+  key: string; // date, ISO week, month, project ID, model ID or session ID
+  label: string | null; // project path or session summary
+  usage: SessionUsage;
+}
+
+export interface UsagePoint {
+  date: string;
+  usage: SessionUsage;
+}
+
+export interface UsageReport {
+  group_by: UsageGroupBy;
+  from: string | null;
+  to: string | null;
+  groups: UsageGroup[];
+  daily: UsagePoint[]; // every day of the range
+  totals: SessionUsage;
+}
+
+export type UsageExportFormat = "csv" | "json";
+
+// Row written by export_usage; columns left empty when not grouped by them.
+// Rows grouped by day, week or month are also split by project.
+export interface UsageRow {
+  date: string; // YYYY-MM-DD, ISO week or YYYY-MM
+  project_path: string;
+  session_id: string;
+  model: string;
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+  cost_usd: number;
+}
+
+export type BudgetPeriod = "daily" | "weekly" | "monthly";
+
+export interface Budget {
+  id: string; // empty for new budgets, assigned on save
+  project_id: string | null; // null covers all projects
+  period: BudgetPeriod;
+  limit_usd: number;
+  thresholds: number[]; // percent of the limit
+}
+
+export interface BudgetSettings {
+  budgets: Budget[];
+  system_notifications: boolean;
+}
+
+export interface BudgetStatus {
+  budget: Budget;
+  period_key: string; // 2025-06-01, 2025-W22 or 2025-06
+  period_start: string;
+  spent_usd: number;
+  percent: number;
+}
+
+// Payload of the "budget-alert" event
+export interface BudgetAlert {
+  budget_id: string;
+  project_id: string | null;
+  project_path: string | null;
+  period: BudgetPeriod;
+  period_key: string;
+  threshold: number;
+  limit_usd: number;
+  spent_usd: number;
 }
 
 export interface Session {
@@ -58,13 +164,55 @@ export interface Session {
   message_count: number;
   last_modified: number;
   usage?: SessionUsage;
+  source: SessionSource;
+  // Kept by lovcode, see SessionMeta
+  tags: string[];
+  starred: boolean;
+  note: string | null;
+  title: string | null;
 }
 
+export interface SessionMeta {
+  tags: string[];
+  starred: boolean;
+  note: string | null;
+  title: string | null; // shown instead of the summary
+}
+
+// Omitted fields are kept, an empty note or title clears it
+export interface SessionMetaUpdate {
+  tags?: string[];
+  starred?: boolean;
+  note?: string;
+  title?: string;
+}
+
+export interface TagCount {
+  tag: string;
+  count: number;
+}
+
+// Coding agent that wrote the session
+export type SessionSource = "claude" | "codex" | "gemini";
+
 export interface SessionUsageEntry {
   session_id: string;
   usage: SessionUsage;
 }
 
+export interface SubagentSession {
+  id: string;
+  agent_id: string;
+  parent_session_id: string;
+  tool_use_id: string | null;
+  description: string | null;
+  subagent_type: string | null;
+  summary: string | null;
+  message_count: number;
+  last_modified: number;
+  usage: SessionUsage;
+}
+
 export interface Message {
   uuid: string;
   role: string;
@@ -75,6 +223,100 @@ export interface Message {
   line_number: number;
 }
 
+export type ContentBlock =
+  | { type: "text"; text: string }
+  | { type: "thinking"; thinking: string }
+  | { type: "tool_use"; id: string; name: string; input: unknown; result_uuid: string | null }
+  | {
+      type: "tool_result";
+      tool_use_id: string;
+      tool_name: string | null;
+      is_error: boolean;
+      content: ContentBlock[];
+    }
+  | { type: "image"; media_type: string | null; source: string };
+
+export interface TranscriptMessage {
+  uuid: string;
+  parent_uuid: string | null;
+  is_sidechain: boolean;
+  role: string;
+  timestamp: string;
+  is_meta: boolean;
+  line_number: number;
+  blocks: ContentBlock[];
+}
+
+export interface TreeNode {
+  uuid: string;
+  parent_uuid: string | null;
+  line_type: string;
+  role: string | null;
+  timestamp: string;
+  line_number: number;
+  is_sidechain: boolean;
+  preview: string;
+  children: string[];
+  is_active: boolean;
+}
+
+export interface ConversationBranch {
+  leaf_uuid: string;
+  fork_uuid: string | null;
+  depth: number;
+  timestamp: string;
+  is_active: boolean;
+  is_sidechain: boolean;
+}
+
+export interface ConversationTree {
+  nodes: TreeNode[];
+  roots: string[];
+  branches: ConversationBranch[];
+  active_leaf: string | null;
+}
+
+export type ExportFormat = "markdown" | "html" | "json";
+
+export interface ExportOptions {
+  redact_secrets?: boolean;
+  redact_home?: boolean;
+  exclude_thinking?: boolean;
+  exclude_tools?: boolean;
+  include_meta?: boolean;
+}
+
+// Archived sessions are kept until restored, trashed ones purged after retention_days
+export type RemovalKind = "archived" | "trashed";
+
+export interface TrashedFile {
+  original_path: string;
+  stored_name: string;
+}
+
+export interface TrashEntry {
+  id: string;
+  kind: RemovalKind;
+  project_id: string;
+  session_id: string;
+  summary: string | null;
+  removed_at: number;
+  files: TrashedFile[];
+}
+
+export interface TrashSettings {
+  retention_days: number; // 0 keeps trashed sessions until restored
+}
+
+export type PageDirection = "forward" | "backward";
+
+export interface MessagesPage {
+  messages: Message[];
+  prev_cursor: number | null;
+  next_cursor: number | null;
+  total_lines: number;
+}
+
 export interface ChatMessage {
   uuid: string;
   role: string;
@@ -84,11 +326,24 @@ export interface ChatMessage {
   project_path: string;
   session_id: string;
   session_summary: string | null;
+  parent_session_id: string | null; // set for messages of subagents
+}
+
+export interface HighlightRange {
+  start: number;
+  end: number;
+}
+
+export interface SearchSnippet {
+  fragment: string;
+  highlights: HighlightRange[];
 }
 
 export interface SearchResult {
   uuid: string;
   content: string;
+  snippet: SearchSnippet;
+  line_number: number;
   role: string;
   project_id: string;
   project_path: string;
@@ -96,6 +351,51 @@ export interface SearchResult {
   session_summary: string | null;
   timestamp: string;
   score: number;
+  is_subagent: boolean;
+  parent_session_id: string | null;
+  source: SessionSource;
+  tags: string[];
+}
+
+export type QueryMode = "strict" | "lenient" | "fuzzy" | "prefix";
+
+export interface SearchResponse {
+  results: SearchResult[];
+  warnings: string[];
+}
+
+// Error returned by the search commands
+export interface SearchIndexError {
+  kind: "not_built" | "schema_mismatch" | "other";
+  message: string;
+}
+
+export interface SessionSearchHit {
+  session_id: string;
+  project_id: string;
+  project_path: string;
+  session_summary: string | null;
+  score: number;
+  hit_count: number;
+  best_match: SearchResult;
+}
+
+export interface SessionSearchResponse {
+  items: SessionSearchHit[];
+  total: number;
+  warnings: string[];
+}
+
+export interface SearchFilters {
+  project_id?: string;
+  session_id?: string;
+  role?: "user" | "assistant";
+  from?: string;
+  to?: string;
+  has_tool?: boolean;
+  tool_name?: string;
+  is_subagent?: boolean;
+  tags?: string[]; // sessions carrying all of these tags
 }
 
 export interface ChatsResponse {
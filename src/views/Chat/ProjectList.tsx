@@ -8,7 +8,7 @@ import { useAppConfig } from "../../context";
 import { VirtualChatList } from "./VirtualChatList";
 import { formatRelativeTime, useReadableText } from "./utils";
 import { useInvokeQuery } from "../../hooks";
-import type { Project, Session, ChatMessage, SearchResult, ChatsResponse } from "../../types";
+import type { Project, Session, ChatMessage, SearchResult, SearchResponse, SearchIndexError, ChatsResponse } from "../../types";
 
 interface ProjectListProps {
   onSelectProject: (p: Project) => void;
@@ -88,11 +88,14 @@ export function ProjectList({ onSelectProject, onSelectSession, onSelectChat }:
     const timer = setTimeout(async () => {
       setSearching(true);
       try {
-        const results = await invoke<SearchResult[]>("search_chats", { query: searchQuery, limit: 50 });
-        setSearchResults(results);
+        const response = await invoke<SearchResponse>("search_chats", { query: searchQuery, limit: 50 });
+        setSearchResults(response.results);
       } catch (e) {
-        if (String(e).includes("not built")) {
+        const error = e as SearchIndexError;
+        if (error.kind === "not_built") {
           setIndexStatus("Search index not built. Click 'Build Index' to create it.");
+        } else if (error.kind === "schema_mismatch") {
+          setIndexStatus("Search index is outdated and is being rebuilt. Try again shortly.");
         }
         setSearchResults([]);
       } finally {
@@ -271,7 +274,7 @@ export function ProjectList({ onSelectProject, onSelectSession, onSelectChat }:
               onClick={() => onSelectSession(session)}
               className="w-full text-left bg-card rounded-xl p-4 border border-border hover:border-primary transition-colors"
             >
-              <p className="font-medium text-ink line-clamp-2">{toReadable(session.summary) || "Untitled session"}</p>
+              <p className="font-medium text-ink line-clamp-2">{session.title || toReadable(session.summary) || "Untitled session"}</p>
               <p className="text-sm text-muted-foreground mt-1 truncate">
                 {session.project_path ? formatPath(session.project_path) : session.project_id}
               </p>
@@ -9,7 +9,7 @@ import { sessionContextTabAtom, sessionSelectModeAtom, hideEmptySessionsAtom, us
 import { useAppConfig } from "../../context";
 import { formatDate, useReadableText } from "./utils";
 import { useInvokeQuery } from "../../hooks";
-import type { Session, ContextFile, Message, SearchResult, SessionUsageEntry, SessionUsage } from "../../types";
+import type { Session, ContextFile, Message, SearchResult, SearchFilters, SearchResponse, SessionUsageEntry, SessionUsage } from "../../types";
 
 interface SessionListProps {
   projectId: string;
@@ -101,8 +101,9 @@ export function SessionList({ projectId, projectPath, onBack, onSelect }: Sessio
     const timer = setTimeout(async () => {
       setSearching(true);
       try {
-        const results = await invoke<SearchResult[]>("search_chats", { query: searchQuery, limit: 50, projectId });
-        setSearchResults(results);
+        const filters: SearchFilters = { project_id: projectId };
+        const response = await invoke<SearchResponse>("search_chats", { query: searchQuery, limit: 50, filters });
+        setSearchResults(response.results);
       } catch {
         setSearchResults([]);
       } finally {
@@ -157,7 +158,11 @@ generator: "Lovcode"
       const parts: string[] = [];
       for (let i = 0; i < selected.length; i++) {
         const session = selected[i];
-        const allMessages = await invoke<Message[]>("get_session_messages", { projectId, sessionId: session.id });
+        const allMessages = await invoke<Message[]>("get_session_messages", {
+          projectId,
+          sessionId: session.id,
+          source: session.source,
+        });
         const messages = userPromptsOnly ? allMessages.filter((m) => m.role === "user") : allMessages;
         const sessionMd = messages
           .map((m) => {
@@ -369,7 +374,7 @@ generator: "Lovcode"
                 >
                   <div className="flex items-start justify-between gap-2">
                     <div className="flex-1 min-w-0">
-                      <p className="font-medium text-ink line-clamp-2">{toReadable(session.summary) || "Untitled session"}</p>
+                      <p className="font-medium text-ink line-clamp-2">{session.title || toReadable(session.summary) || "Untitled session"}</p>
                       <div className="flex items-center gap-3 mt-2 text-sm text-muted-foreground">
                         <span>{session.message_count} messages</span>
                         <span>·</span>
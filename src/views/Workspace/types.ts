@@ -80,6 +80,13 @@ export interface WorkspaceData {
   feature_counter?: number;
 }
 
+/** Panel running a resumed or forked history session (`open_session_in_panel`) */
+export interface SessionPanel {
+  workspace_project_id: string;
+  feature_id: string;
+  panel: PanelState;
+}
+
 // ============================================================================
 // Git Types
 // ============================================================================